@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::{
+    commands::error::CommandsError,
+    shell::{Shell, ShellError, is_valid_alias_name},
+};
+
+pub(crate) fn invoke_alias(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let mut names = args;
+    while let Some(flags) = names.first().and_then(|arg| arg.strip_prefix('-')) {
+        names = &names[1..];
+        if flags == "-" {
+            break;
+        }
+        // `-p` prints all aliases, which is also done when no names are given
+        if let Some(flag) = flags.chars().find(|&flag| flag != 'p') {
+            return Err(CommandsError::InvalidOption("alias", format!("-{flag}")).into());
+        }
+    }
+
+    let mut buf = String::new();
+    if names.is_empty() || args.iter().any(|arg| arg == "-p") {
+        for (name, value) in &shell.aliases {
+            let _ = writeln!(buf, "{}", alias_definition(name, value));
+        }
+    }
+    for arg in names {
+        match arg.split_once('=') {
+            Some((name, value)) if is_valid_alias_name(name) => {
+                shell.aliases.insert(name.to_string(), value.to_string());
+            }
+            Some((name, _)) => {
+                eprintln!("alias: `{name}': invalid alias name");
+                shell.last_status = 1;
+            }
+            None => {
+                if let Some(value) = shell.aliases.get(arg) {
+                    let _ = writeln!(buf, "{}", alias_definition(arg, value));
+                } else {
+                    eprintln!("alias: {arg}: not found");
+                    shell.last_status = 1;
+                }
+            }
+        }
+    }
+    Ok(if buf.is_empty() { None } else { Some(buf) })
+}
+
+pub(crate) fn invoke_unalias(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let mut names = args;
+    while let Some(flags) = names.first().and_then(|arg| arg.strip_prefix('-')) {
+        names = &names[1..];
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'a' => shell.aliases.clear(),
+                _ => return Err(CommandsError::InvalidOption("unalias", format!("-{flag}")))?,
+            }
+        }
+    }
+    for name in names {
+        if shell.aliases.remove(name).is_none() {
+            eprintln!("unalias: {name}: not found");
+            shell.last_status = 1;
+        }
+    }
+    Ok(None)
+}
+
+/// Format an alias as the `alias` command defining it, always single quoting the value like bash
+fn alias_definition(name: &str, value: &str) -> String {
+    format!("alias {name}='{}'", value.replace('\'', r"'\''"))
+}
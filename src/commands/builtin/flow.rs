@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::{error::CommandsError, find_file},
+    shell::{Flow, Shell, ShellError},
+};
+
+/// Leave the shell with the status given, or with `$?`, a status that is not a number is reported and gives 2
+pub(crate) fn invoke_exit(args: &[String], shell: &mut Shell) -> Option<String> {
+    if let Some(arg) = args.first() {
+        if let Ok(status) = arg.parse::<i64>() {
+            // truncated to a byte like `return`, so `exit -1` gives 255
+            #[allow(clippy::cast_possible_truncation)]
+            let status = status.rem_euclid(256) as i32;
+            shell.last_status = status;
+        } else {
+            let err = CommandsError::NumericArgumentRequired("exit", arg.clone());
+            eprintln!("{}{err}", shell.error_prefix());
+            shell.last_status = 2;
+        }
+    }
+    shell.flow = Some(Flow::Exit);
+    None
+}
+
+pub(crate) fn invoke_return(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    if shell.call_depth == 0 && shell.source_depth == 0 {
+        return Err(CommandsError::ReturnOutsideFunction);
+    }
+    if let Some(arg) = args.first() {
+        let status = arg
+            .parse::<i64>()
+            .map_err(|_| CommandsError::NumericArgumentRequired("return", arg.clone()))?;
+        // statuses are truncated to a byte like in bash, so `return -1` gives 255
+        #[allow(clippy::cast_possible_truncation)]
+        let status = status.rem_euclid(256) as i32;
+        shell.last_status = status;
+    }
+    shell.flow = Some(Flow::Return);
+    Ok(None)
+}
+
+pub(crate) fn invoke_source(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let Some(name) = args.first() else {
+        return Err(CommandsError::FilenameRequired.into());
+    };
+    // without a slash the file is looked up on PATH, falling back to the current directory
+    let path = if name.contains('/') {
+        PathBuf::from(name)
+    } else {
+        find_file(name, shell.vars.value("PATH")).unwrap_or_else(|| PathBuf::from(name))
+    };
+    shell.source(&path, &args[1..])?;
+    Ok(None)
+}
@@ -1,27 +1,59 @@
-use rustyline::history::FileHistory;
-
-use crate::commands::{
-    Builtin,
-    builtin::{
-        fs::{invoke_cd, invoke_pwd},
-        history::invoke_history,
-        string::{invoke_echo, invoke_type},
+use crate::{
+    commands::{
+        Builtin,
+        builtin::{
+            alias::{invoke_alias, invoke_unalias},
+            flow::{invoke_exit, invoke_return, invoke_source},
+            fs::{invoke_cd, invoke_pwd},
+            history::invoke_history,
+            jobs::{invoke_bg, invoke_disown, invoke_fg, invoke_jobs},
+            kill::invoke_kill,
+            params::{invoke_set, invoke_shift, invoke_shopt},
+            string::{invoke_echo, invoke_type},
+            times::invoke_times,
+            trap::invoke_trap,
+            vars::{
+                invoke_declare, invoke_export, invoke_local, invoke_printenv, invoke_readonly,
+                invoke_unset,
+            },
+            wait::invoke_wait,
+        },
     },
-    error::CommandsError,
+    shell::{Shell, ShellError},
 };
 
 pub(crate) fn invoke_builtin(
     cmd: Builtin,
     args: &[String],
-    history: &mut FileHistory,
-) -> Result<Option<String>, CommandsError> {
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
     match cmd {
         Builtin::Echo => Ok(Some(invoke_echo(args))),
-        Builtin::Exit => unreachable!(), // unreachable as we check for exit in main beforehand
-        Builtin::Tipe => Ok(Some(invoke_type(args))),
+        Builtin::Exit => Ok(invoke_exit(args, shell)),
+        Builtin::Tipe => Ok(Some(invoke_type(args, shell))),
         Builtin::Pwd => Ok(Some(invoke_pwd(args)?)),
-        Builtin::Cd => invoke_cd(args),
-        Builtin::History => Ok(invoke_history(args, history)),
-        Builtin::Jobs => Ok(None),
+        Builtin::Cd => Ok(invoke_cd(args)?),
+        Builtin::History => Ok(invoke_history(args, shell.rl.history_mut())),
+        Builtin::Jobs => Ok(invoke_jobs(args, shell)?),
+        Builtin::Fg => Ok(invoke_fg(args, shell)?),
+        Builtin::Bg => Ok(invoke_bg(args, shell)?),
+        Builtin::Wait => Ok(invoke_wait(args, shell)?),
+        Builtin::Kill => Ok(invoke_kill(args, shell)?),
+        Builtin::Disown => Ok(invoke_disown(args, shell)?),
+        Builtin::Trap => Ok(invoke_trap(args, shell)?),
+        Builtin::Return => Ok(invoke_return(args, shell)?),
+        Builtin::Set => Ok(invoke_set(args, shell)?),
+        Builtin::Shopt => Ok(invoke_shopt(args, shell)?),
+        Builtin::Shift => Ok(invoke_shift(args, shell)?),
+        Builtin::Declare => invoke_declare(args, shell),
+        Builtin::Local => invoke_local(args, shell),
+        Builtin::Readonly => invoke_readonly(args, shell),
+        Builtin::Unset => invoke_unset(args, shell),
+        Builtin::Export => invoke_export(args, shell),
+        Builtin::Printenv => Ok(invoke_printenv(args, shell)),
+        Builtin::Alias => invoke_alias(args, shell),
+        Builtin::Unalias => invoke_unalias(args, shell),
+        Builtin::Source => invoke_source(args, shell),
+        Builtin::Times => Ok(Some(invoke_times())),
     }
 }
@@ -0,0 +1,307 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write as _},
+};
+
+use crate::{
+    commands::error::CommandsError,
+    shell::{JobState, JobTable, Shell},
+};
+
+/// Resolve a job spec to the id of the job it names, no spec means the current job
+/// `%n` or `n` names job `n`, `%+`, `%%` and `%` the current job, `%-` the previous one,
+/// `%name` the job whose command starts with `name` and `%?text` the one whose command contains `text`
+pub(super) fn find_job(
+    builtin: &'static str,
+    spec: Option<&str>,
+    jobs: &JobTable,
+) -> Result<usize, CommandsError> {
+    let no_such_job = || CommandsError::NoSuchJob(builtin, spec.unwrap_or("current").into());
+    let Some(spec) = spec else {
+        return jobs.current().ok_or_else(no_such_job);
+    };
+    // a bare number is accepted as well as `%n`
+    let Some(pattern) = spec
+        .strip_prefix('%')
+        .or_else(|| spec.parse::<usize>().is_ok().then_some(spec))
+    else {
+        return Err(no_such_job());
+    };
+    if let Ok(id) = pattern.parse::<usize>() {
+        return jobs.get(id).map(|job| job.id).ok_or_else(no_such_job);
+    }
+    let matches_command = |command: &str| match pattern.strip_prefix('?') {
+        Some(text) => command.contains(text),
+        None => command.starts_with(pattern),
+    };
+    let matches: Vec<usize> = match pattern {
+        "" | "%" | "+" => return jobs.current().ok_or_else(no_such_job),
+        "-" => return jobs.previous().ok_or_else(no_such_job),
+        _ => jobs
+            .iter()
+            .filter(|job| matches_command(&job.command))
+            .map(|job| job.id)
+            .collect(),
+    };
+    match matches[..] {
+        [id] => Ok(id),
+        [] => Err(no_such_job()),
+        _ => Err(CommandsError::AmbiguousJob(builtin, spec.into())),
+    }
+}
+
+pub(crate) fn invoke_jobs(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let (mut long, mut pids_only, mut running, mut stopped) = (false, false, false, false);
+    let mut specs = args;
+    while let Some((arg, rest)) = specs.split_first() {
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            break;
+        };
+        specs = rest;
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'l' => long = true,
+                'p' => pids_only = true,
+                'r' => running = true,
+                's' => stopped = true,
+                _ => return Err(CommandsError::InvalidOption("jobs", format!("-{flag}"))),
+            }
+        }
+    }
+
+    shell.jobs.update();
+    let selected_ids = specs
+        .iter()
+        .map(|spec| find_job("jobs", Some(spec), &shell.jobs))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut out = String::new();
+    let mut listed = vec![];
+    for job in shell.jobs.iter() {
+        let selected = match job.state {
+            _ if !selected_ids.is_empty() && !selected_ids.contains(&job.id) => false,
+            JobState::Running => !stopped,
+            JobState::Stopped(_) => !running,
+            JobState::Done(_) | JobState::Killed(..) => !running && !stopped,
+        };
+        if !selected {
+            continue;
+        }
+        if pids_only {
+            let _ = writeln!(out, "{}", job.pid());
+        } else {
+            let _ = writeln!(out, "{}", shell.jobs.format(job, long));
+        }
+        listed.push(job.id);
+    }
+    // like the notification before a prompt, listing a job reports its change and a finished job for the last time
+    for id in listed {
+        match shell.jobs.get_mut(id) {
+            Some(job) if job.state.is_finished() => {
+                shell.jobs.remove(id);
+            }
+            Some(job) => job.changed = false,
+            None => {}
+        }
+    }
+    Ok(Some(out))
+}
+
+pub(crate) fn invoke_fg(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    if !shell.interactive {
+        return Err(CommandsError::NoJobControl("fg"));
+    }
+    shell.jobs.update();
+    let id = find_job("fg", args.first().map(String::as_str), &shell.jobs)?;
+    let Some(job) = shell.jobs.get(id) else {
+        return Err(CommandsError::NoSuchJob(
+            "fg",
+            args.first().cloned().unwrap_or_default(),
+        ));
+    };
+    if job.state.is_finished() {
+        shell.jobs.remove(id);
+        return Err(CommandsError::JobTerminated("fg"));
+    }
+    // the command is echoed before the job takes over the terminal
+    println!("{}", job.command);
+    let _ = io::stdout().flush();
+    shell.foreground_job(id);
+    Ok(None)
+}
+
+pub(crate) fn invoke_bg(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    if !shell.interactive {
+        return Err(CommandsError::NoJobControl("bg"));
+    }
+    shell.jobs.update();
+    let specs: Vec<Option<&str>> = if args.is_empty() {
+        vec![None]
+    } else {
+        args.iter().map(|arg| Some(arg.as_str())).collect()
+    };
+    let mut out = String::new();
+    for spec in specs {
+        let id = find_job("bg", spec, &shell.jobs)?;
+        let Some(job) = shell.jobs.get(id) else {
+            continue;
+        };
+        match job.state {
+            JobState::Stopped(_) => {
+                shell.background_job(id);
+                if let Some(job) = shell.jobs.get(id) {
+                    let _ = writeln!(out, "[{id}]{} {} &", shell.jobs.mark(id), job.command);
+                }
+            }
+            JobState::Running => eprintln!("bg: job {id} already in background"),
+            JobState::Done(_) | JobState::Killed(..) => {
+                shell.jobs.remove(id);
+                return Err(CommandsError::JobTerminated("bg"));
+            }
+        }
+    }
+    Ok(Some(out))
+}
+
+/// `disown [-h] [-ar] [jobspec ...]`: forget jobs, or with `-h` only keep them from being sent `SIGHUP` on exit
+/// `-a` applies to every job and `-r` to the running ones, without either the current job is used
+pub(crate) fn invoke_disown(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let (mut nohup, mut all, mut running) = (false, false, false);
+    let mut specs = args;
+    while let Some((arg, rest)) = specs.split_first() {
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            break;
+        };
+        specs = rest;
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'h' => nohup = true,
+                'a' => all = true,
+                'r' => running = true,
+                _ => return Err(CommandsError::InvalidOption("disown", format!("-{flag}"))),
+            }
+        }
+    }
+
+    shell.jobs.update();
+    let ids: Vec<usize> = if !specs.is_empty() {
+        specs
+            .iter()
+            .map(|spec| find_job("disown", Some(spec), &shell.jobs))
+            .collect::<Result<_, _>>()?
+    } else if all || running {
+        shell
+            .jobs
+            .iter()
+            .filter(|job| !running || job.state == JobState::Running)
+            .map(|job| job.id)
+            .collect()
+    } else {
+        vec![find_job("disown", None, &shell.jobs)?]
+    };
+    for id in ids {
+        if nohup {
+            if let Some(job) = shell.jobs.get_mut(id) {
+                job.nohup = true;
+            }
+        } else {
+            shell.jobs.remove(id);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use nix::unistd::Pid;
+
+    use super::*;
+    use crate::shell::Process;
+
+    fn jobs(commands: &[&str]) -> JobTable {
+        let mut jobs = JobTable::default();
+        for (pid, command) in (1000..).zip(commands) {
+            jobs.add(
+                vec![Process::new(Pid::from_raw(pid))],
+                None,
+                (*command).into(),
+            );
+        }
+        jobs
+    }
+
+    fn find(spec: &str, jobs: &JobTable) -> Result<usize, String> {
+        find_job("fg", Some(spec), jobs).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn job_numbers() {
+        let jobs = jobs(&["sleep 10", "vim notes", "sleep 20"]);
+        assert_eq!(find("%2", &jobs), Ok(2));
+        assert_eq!(find("3", &jobs), Ok(3));
+        assert_eq!(find("%4", &jobs), Err("fg: %4: no such job".into()));
+        assert_eq!(find("x", &jobs), Err("fg: x: no such job".into()));
+    }
+
+    #[test]
+    fn current_and_previous_jobs() {
+        let mut jobs = jobs(&["sleep 10", "vim notes", "sleep 20"]);
+        for spec in ["%%", "%+", "%"] {
+            assert_eq!(find(spec, &jobs), Ok(3));
+        }
+        assert_eq!(find_job("fg", None, &jobs).ok(), Some(3));
+        assert_eq!(find("%-", &jobs), Ok(2));
+        jobs.touch(1);
+        assert_eq!(find("%+", &jobs), Ok(1));
+        assert_eq!(find("%-", &jobs), Ok(3));
+
+        let empty = JobTable::default();
+        assert_eq!(find("%+", &empty), Err("fg: %+: no such job".into()));
+        assert_eq!(
+            find_job("fg", None, &empty).map_err(|e| e.to_string()),
+            Err("fg: current: no such job".into())
+        );
+        assert_eq!(
+            find("%-", &self::jobs(&["sleep 10"])),
+            Err("fg: %-: no such job".into())
+        );
+    }
+
+    #[test]
+    fn jobs_by_command() {
+        let jobs = jobs(&["sleep 10", "vim notes", "sleep 20"]);
+        assert_eq!(find("%vim", &jobs), Ok(2));
+        assert_eq!(find("%?20", &jobs), Ok(3));
+        assert_eq!(find("%?note", &jobs), Ok(2));
+        assert_eq!(
+            find("%sleep", &jobs),
+            Err("fg: %sleep: ambiguous job spec".into())
+        );
+        assert_eq!(
+            find("%?sleep", &jobs),
+            Err("fg: %?sleep: ambiguous job spec".into())
+        );
+        assert_eq!(find("%notes", &jobs), Err("fg: %notes: no such job".into()));
+        assert_eq!(
+            find("%?emacs", &jobs),
+            Err("fg: %?emacs: no such job".into())
+        );
+    }
+}
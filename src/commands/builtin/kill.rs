@@ -0,0 +1,169 @@
+use std::fmt::Write as _;
+
+use nix::{
+    sys::signal::{Signal, kill},
+    unistd::Pid,
+};
+
+use crate::{
+    commands::{builtin::jobs::find_job, error::CommandsError},
+    shell::{JobState, Shell, ShellError, parse_signal, signal_name},
+};
+
+const USAGE: &str =
+    "kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]";
+
+/// Send a signal, `SIGTERM` unless given, to processes or to the process groups of jobs
+pub(crate) fn invoke_kill(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let mut signal = Some(Signal::SIGTERM);
+    let mut rest = args;
+    match args {
+        [flag, specs @ ..] if flag == "-l" || flag == "-L" => return list_signals(specs).map(Some),
+        [flag, spec, targets @ ..] if flag == "-s" || flag == "-n" => {
+            signal = signal_arg(spec)?;
+            rest = targets;
+        }
+        [flag, targets @ ..] if flag == "--" => rest = targets,
+        [spec, targets @ ..] if spec.len() > 1 && spec.starts_with('-') => {
+            signal = signal_arg(&spec[1..])?;
+            rest = targets;
+        }
+        _ => {}
+    }
+    if let [flag, targets @ ..] = rest
+        && flag == "--"
+    {
+        rest = targets;
+    }
+    if rest.is_empty() {
+        return Err(CommandsError::Usage("kill", USAGE));
+    }
+
+    shell.jobs.update();
+    shell.last_status = 0;
+    for target in rest {
+        if let Err(e) = signal_target(target, signal, shell) {
+            eprintln!("{}{}", shell.error_prefix(), ShellError::from(e));
+            shell.last_status = 1;
+        }
+    }
+    Ok(None)
+}
+
+/// The signal named by `spec`, where signal 0 only checks whether the processes exist
+fn signal_arg(spec: &str) -> Result<Option<Signal>, CommandsError> {
+    if spec == "0" {
+        return Ok(None);
+    }
+    parse_signal(spec)
+        .map(Some)
+        .ok_or_else(|| CommandsError::InvalidSignal("kill", spec.into()))
+}
+
+fn signal_target(
+    target: &str,
+    signal: Option<Signal>,
+    shell: &mut Shell,
+) -> Result<(), CommandsError> {
+    let failed = |e: nix::Error| CommandsError::SignalFailed("kill", target.into(), e.desc());
+    if target.starts_with('%') {
+        let id = find_job("kill", Some(target), &shell.jobs)?;
+        let Some(job) = shell.jobs.get(id) else {
+            return Ok(());
+        };
+        job.signal(signal).map_err(failed)?;
+        // a stopped job only acts on a signal that ends it once it runs again
+        if matches!(job.state, JobState::Stopped(_))
+            && matches!(signal, Some(Signal::SIGTERM | Signal::SIGHUP))
+        {
+            job.signal(Signal::SIGCONT).map_err(failed)?;
+        }
+        return Ok(());
+    }
+    // a negative pid names a process group
+    let pid = target
+        .parse()
+        .map_err(|_| CommandsError::NotAPidOrJob("kill", target.into()))?;
+    kill(Pid::from_raw(pid), signal).map_err(failed)
+}
+
+/// `kill -l`: list all signals, or translate the given signal names to numbers and numbers or exit statuses to names
+pub(super) fn list_signals(specs: &[String]) -> Result<String, CommandsError> {
+    let mut out = String::new();
+    if specs.is_empty() {
+        for (i, signal) in Signal::iterator().enumerate() {
+            let separator = if i % 5 == 4 { '\n' } else { '\t' };
+            let _ = write!(
+                out,
+                "{:2}) SIG{}{separator}",
+                signal as i32,
+                signal_name(signal)
+            );
+        }
+        if out.ends_with('\t') {
+            out.pop();
+            out.push('\n');
+        }
+        return Ok(out);
+    }
+    for spec in specs {
+        // exit statuses of commands killed by a signal are accepted as well as signal numbers
+        let line = if let Ok(number) = spec.parse::<i32>() {
+            Signal::try_from(if number > 128 { number - 128 } else { number })
+                .ok()
+                .map(|signal| signal_name(signal).to_string())
+        } else {
+            parse_signal(spec).map(|signal| (signal as i32).to_string())
+        }
+        .ok_or_else(|| CommandsError::InvalidSignal("kill", spec.clone()))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(specs: &[&str]) -> Result<String, String> {
+        let specs: Vec<_> = specs.iter().map(ToString::to_string).collect();
+        list_signals(&specs).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn list_every_signal() {
+        let Ok(listing) = list(&[]) else {
+            panic!("listing every signal failed");
+        };
+        let lines: Vec<_> = listing.lines().collect();
+        assert_eq!(
+            lines.first(),
+            Some(&" 1) SIGHUP\t 2) SIGINT\t 3) SIGQUIT\t 4) SIGILL\t 5) SIGTRAP")
+        );
+        // five signals to a line, the last one possibly shorter
+        let signals = Signal::iterator().count();
+        assert_eq!(lines.len(), signals.div_ceil(5));
+        assert!(listing.ends_with('\n'));
+        assert!(lines.iter().all(|line| line.split('\t').count() <= 5));
+        assert!(listing.contains("15) SIGTERM"));
+    }
+
+    #[test]
+    fn translate_signals() {
+        assert_eq!(list(&["9", "TERM", "sigint"]), Ok("KILL\n15\n2\n".into()));
+        // the status of a command killed by a signal names that signal
+        assert_eq!(list(&["143"]), Ok("TERM\n".into()));
+        assert_eq!(
+            list(&["INT", "FOO"]),
+            Err("kill: FOO: invalid signal specification".into())
+        );
+        assert_eq!(
+            list(&["0"]),
+            Err("kill: 0: invalid signal specification".into())
+        );
+    }
+}
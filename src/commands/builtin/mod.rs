@@ -1,7 +1,16 @@
+mod alias;
+mod flow;
 mod fs;
 mod history;
 mod invoke;
+mod jobs;
+mod kill;
+mod params;
 mod string;
+mod times;
+mod trap;
+mod vars;
+mod wait;
 
 use std::str::FromStr;
 use thiserror::Error;
@@ -9,7 +18,11 @@ use thiserror::Error;
 pub(crate) use invoke::invoke_builtin;
 
 /// Constant array of the names of the built-in commands, used for autocompletion and `type` command
-pub const BUILTIN_COMMANDS: [&str; 7] = ["echo", "exit", "type", "pwd", "cd", "history", "jobs"];
+pub const BUILTIN_COMMANDS: [&str; 29] = [
+    "echo", "exit", "type", "pwd", "cd", "history", "jobs", "fg", "bg", "wait", "kill", "disown",
+    "trap", "return", "shopt", "set", "shift", "declare", "typeset", "local", "readonly", "unset",
+    "export", "printenv", "alias", "unalias", "source", ".", "times",
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Enum representing the commmands built into this shell
@@ -33,8 +46,103 @@ pub enum Builtin {
     /// - `history -w <file>` — write history
     /// - `history -a <file>` — append history
     History,
-    /// Command for interacting with jobs
+    /// List the jobs of the shell
+    /// # Usage
+    ///
+    /// - `jobs [-lprs] [jobspec ...]` — `-l` adds process ids, `-p` prints only process ids,
+    ///   `-r` and `-s` restrict the list to running or stopped jobs
+    ///
+    /// Job specs are `%n`, `%+` (or `%%`) for the current job, `%-` for the previous one,
+    /// `%name` for a command starting with `name` and `%?text` for one containing `text`
     Jobs,
+    /// Continue a job in the foreground and wait for it, `fg [jobspec]`
+    Fg,
+    /// Continue stopped jobs in the background, `bg [jobspec ...]`
+    Bg,
+    /// Wait for jobs to finish
+    /// # Usage
+    ///
+    /// - `wait` — wait for all jobs
+    /// - `wait <jobspec|pid ...>` — wait for the given jobs, `$?` is the status of the last one
+    /// - `wait -n [jobspec|pid ...]` — wait for the next of the jobs to finish and return its status
+    Wait,
+    /// Send a signal to processes or jobs
+    /// # Usage
+    ///
+    /// - `kill [-s sigspec | -n signum | -sigspec] pid | jobspec ...` — `SIGTERM` by default,
+    ///   a job is signalled as a whole process group and a negative pid names a process group
+    /// - `kill -l [sigspec | status]` — list the signals, or convert between names and numbers
+    Kill,
+    /// Remove jobs from the job table so the shell no longer reports or hangs them up
+    /// # Usage
+    ///
+    /// - `disown [-ar] [jobspec ...]` — forget the given jobs, all jobs (`-a`), the running ones (`-r`)
+    ///   or the current job
+    /// - `disown -h [jobspec ...]` — keep the jobs but do not send them `SIGHUP` when the shell exits
+    Disown,
+    /// Run commands when the shell receives a signal or on the pseudo-signals `EXIT`, `DEBUG`, `ERR` and `RETURN`
+    /// # Usage
+    ///
+    /// - `trap action sigspec ...` — run `action` between commands once a signal arrived, an empty action ignores it
+    /// - `trap - sigspec ...` — reset the signals to their usual action and remove the traps
+    /// - `trap [-p [sigspec ...]]` — print the traps as re-usable `trap` commands, `trap -l` lists the signals
+    ///
+    /// `EXIT` runs when the shell exits, `DEBUG` before each simple command, `ERR` after a failing simple
+    /// command and `RETURN` when a function or sourced file finishes
+    Trap,
+    /// Return from the current function, optionally with the given exit status
+    Return,
+    /// Set shell options and positional parameters
+    /// # Usage
+    ///
+    /// - `set -- <args>` — replace the positional parameters with `args`
+    Set,
+    /// Turn `shopt` options on (`-s`) or off (`-u`), or list them, `-p` as re-usable commands
+    Shopt,
+    /// Shift the positional parameters `n` (default 1) places to the left
+    Shift,
+    /// Declare variables and give them attributes, also available as `typeset`
+    /// # Usage
+    ///
+    /// - `declare [-irxlu] name[=value]` — set attributes (`+` instead of `-` removes them)
+    /// - `declare -p [name]` — print variables as re-usable `declare` commands
+    /// - `declare -f [name]` / `declare -F` — print function definitions or names
+    Declare,
+    /// Declare variables local to the current function, takes the same options as `declare`
+    Local,
+    /// Mark variables as readonly, `readonly -p` lists them
+    Readonly,
+    /// Unset variables (`-v`) or functions (`-f`)
+    Unset,
+    /// Mark variables as exported to the environment of child processes
+    /// # Usage
+    ///
+    /// - `export name[=value]` — export variables, optionally assigning them
+    /// - `export -n name` — stop exporting variables
+    /// - `export` / `export -p` — print the exported variables as re-usable `declare -x` commands
+    Export,
+    /// Print the environment child processes receive as `name=value` lines, or the values of the given variables
+    Printenv,
+    /// Define aliases with `alias name=value`, or print them in a form that can be read back by the shell
+    Alias,
+    /// Remove aliases, `unalias -a` removes all of them
+    Unalias,
+    /// Execute a file in the current shell, also available as `.`
+    /// # Usage
+    ///
+    /// - `source file [args]` — `file` is looked up on $PATH when it contains no slash,
+    ///   `args` become the positional parameters while it runs
+    Source,
+    /// Print the user and system time used by the shell and by its children
+    Times,
+}
+
+impl Builtin {
+    /// Whether the output of a previous pipeline stage is passed to this builtin as extra arguments,
+    /// only builtins that print something about their arguments take it, the others ignore it like in bash
+    pub(crate) fn takes_piped_words(self) -> bool {
+        matches!(self, Builtin::Echo | Builtin::Tipe | Builtin::Printenv)
+    }
 }
 
 #[derive(Debug, Error, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -55,6 +163,26 @@ impl FromStr for Builtin {
             "cd" => Ok(Builtin::Cd),
             "history" => Ok(Builtin::History),
             "jobs" => Ok(Builtin::Jobs),
+            "fg" => Ok(Builtin::Fg),
+            "bg" => Ok(Builtin::Bg),
+            "wait" => Ok(Builtin::Wait),
+            "kill" => Ok(Builtin::Kill),
+            "disown" => Ok(Builtin::Disown),
+            "trap" => Ok(Builtin::Trap),
+            "return" => Ok(Builtin::Return),
+            "set" => Ok(Builtin::Set),
+            "shopt" => Ok(Builtin::Shopt),
+            "shift" => Ok(Builtin::Shift),
+            "declare" | "typeset" => Ok(Builtin::Declare),
+            "local" => Ok(Builtin::Local),
+            "readonly" => Ok(Builtin::Readonly),
+            "unset" => Ok(Builtin::Unset),
+            "export" => Ok(Builtin::Export),
+            "printenv" => Ok(Builtin::Printenv),
+            "alias" => Ok(Builtin::Alias),
+            "unalias" => Ok(Builtin::Unalias),
+            "source" | "." => Ok(Builtin::Source),
+            "times" => Ok(Builtin::Times),
             _ => Err(FromStrError::UnknownBuiltin(s.to_string())),
         }
     }
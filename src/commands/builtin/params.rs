@@ -0,0 +1,151 @@
+use crate::{
+    commands::{builtin::vars::list_variables, error::CommandsError},
+    shell::{Shell, ShellOption, ShoptOption},
+};
+
+pub(crate) fn invoke_set(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    if args.is_empty() {
+        return Ok(list_variables(shell, |_| true, false));
+    }
+    let mut output = None;
+    let mut args = args.iter().peekable();
+    let mut end_of_options = false;
+    while let Some(arg) = args.next_if(|arg| arg.len() > 1 && arg.starts_with(['-', '+'])) {
+        if arg == "--" {
+            end_of_options = true;
+            break;
+        }
+        let enable = arg.starts_with('-');
+        for flag in arg.chars().skip(1) {
+            if flag == 'o' {
+                // without a name `-o` lists the options and `+o` prints the commands recreating them
+                let Some(name) = args.next() else {
+                    output = Some(list_options(shell, !enable));
+                    continue;
+                };
+                let option = ShellOption::from_name(name)
+                    .ok_or_else(|| CommandsError::InvalidOptionName("set", name.clone()))?;
+                shell.set_option(option, enable);
+            } else {
+                let option = ShellOption::from_flag(flag)
+                    .ok_or_else(|| CommandsError::InvalidOption("set", arg.clone()))?;
+                shell.set_option(option, enable);
+            }
+        }
+    }
+    // options alone leave the positional parameters alone, `--` without arguments clears them
+    let params: Vec<String> = args.cloned().collect();
+    if end_of_options || !params.is_empty() {
+        shell.positional = params;
+    }
+    Ok(output)
+}
+
+fn list_options(shell: &Shell, as_commands: bool) -> String {
+    ShellOption::ALL
+        .into_iter()
+        .map(|option| {
+            let enabled = shell.option(option);
+            if as_commands {
+                let sign = if enabled { '-' } else { '+' };
+                format!("set {sign}o {}\n", option.name())
+            } else {
+                let state = if enabled { "on" } else { "off" };
+                format!("{:<15}\t{state}\n", option.name())
+            }
+        })
+        .collect()
+}
+
+/// `shopt [-pqsu] [name ...]`: turn options on with `-s` or off with `-u`, otherwise list them,
+/// failing when a named option is off
+pub(crate) fn invoke_shopt(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let (mut set, mut unset, mut as_commands, mut quiet) = (false, false, false, false);
+    let mut names = args;
+    while let Some((arg, rest)) = names.split_first() {
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            break;
+        };
+        names = rest;
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                's' => set = true,
+                'u' => unset = true,
+                'p' => as_commands = true,
+                'q' => quiet = true,
+                _ => return Err(CommandsError::InvalidOption("shopt", format!("-{flag}"))),
+            }
+        }
+    }
+    let options = names
+        .iter()
+        .map(|name| {
+            ShoptOption::from_name(name)
+                .ok_or_else(|| CommandsError::InvalidOptionName("shopt", name.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if (set || unset) && !options.is_empty() {
+        for option in options {
+            shell.set_shopt(option, set);
+        }
+        return Ok(None);
+    }
+    // `-s` or `-u` without names list the options that are on or off
+    let listed: Vec<ShoptOption> = if options.is_empty() {
+        ShoptOption::ALL
+            .into_iter()
+            .filter(|&option| !(set || unset) || shell.shopt(option) == set)
+            .collect()
+    } else {
+        options
+    };
+    if listed.iter().any(|&option| !shell.shopt(option)) && !names.is_empty() {
+        shell.last_status = 1;
+    }
+    if quiet {
+        return Ok(None);
+    }
+    let out = listed
+        .into_iter()
+        .map(|option| {
+            let enabled = shell.shopt(option);
+            if as_commands {
+                let flag = if enabled { 's' } else { 'u' };
+                format!("shopt -{flag} {}\n", option.name())
+            } else {
+                let state = if enabled { "on" } else { "off" };
+                format!("{:<15}\t{state}\n", option.name())
+            }
+        })
+        .collect();
+    Ok(Some(out))
+}
+
+pub(crate) fn invoke_shift(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let n = match args.first() {
+        Some(arg) => arg
+            .parse::<usize>()
+            .map_err(|_| CommandsError::NumericArgumentRequired("shift", arg.clone()))?,
+        None => 1,
+    };
+    if n > shell.positional.len() {
+        // like bash, shifting past the end leaves the parameters alone and only fails
+        shell.last_status = 1;
+    } else {
+        shell.positional.drain(..n);
+    }
+    Ok(None)
+}
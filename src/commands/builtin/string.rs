@@ -1,4 +1,7 @@
-use crate::commands::{Builtin, find_exec_file};
+use crate::{
+    commands::{Builtin, find_exec_file},
+    shell::Shell,
+};
 use std::str::FromStr;
 
 pub(crate) fn invoke_echo(cmd_list: &[String]) -> String {
@@ -7,7 +10,7 @@ pub(crate) fn invoke_echo(cmd_list: &[String]) -> String {
     s
 }
 
-pub(crate) fn invoke_type(cmd_list: &[String]) -> String {
+pub(crate) fn invoke_type(cmd_list: &[String], shell: &Shell) -> String {
     use std::fmt::Write;
     let mut buf = String::new();
     for (i, cmd) in cmd_list.iter().enumerate() {
@@ -17,11 +20,15 @@ pub(crate) fn invoke_type(cmd_list: &[String]) -> String {
             cmd.clone()
         };
         let cmd_str = cmd_str.as_str();
-        if Builtin::from_str(cmd).is_ok() {
+        if let Some(value) = shell.aliases.get(cmd) {
+            let _ = write!(buf, "{cmd_str} is aliased to `{value}'");
+        } else if Builtin::from_str(cmd).is_ok() {
             let _ = write!(buf, "{cmd_str} is a shell builtin");
+        } else if let Some(function) = shell.functions.get(cmd) {
+            let _ = write!(buf, "{cmd_str} is a function\n{function}");
         } else {
             // go through every directory and check if a file with the name exist that has exec permissions
-            match find_exec_file(cmd) {
+            match find_exec_file(cmd, shell.vars.value("PATH")) {
                 Ok(Some(file_path)) => {
                     let _ = write!(buf, "{cmd_str} is {}", file_path.display());
                 }
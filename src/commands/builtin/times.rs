@@ -0,0 +1,48 @@
+use nix::sys::resource::UsageWho;
+
+use crate::shell::{cpu_times, format_duration};
+
+/// Print the user and system time used by the shell, then by its children, like bash's `times`
+pub(crate) fn invoke_times() -> String {
+    [UsageWho::RUSAGE_SELF, UsageWho::RUSAGE_CHILDREN]
+        .into_iter()
+        .map(|who| {
+            let (user, sys) = cpu_times(who);
+            format!(
+                "{} {}\n",
+                format_duration(user, 3, true),
+                format_duration(sys, 3, true)
+            )
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_lines() {
+        let output = invoke_times();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let times: Vec<_> = line.split(' ').collect();
+            assert_eq!(times.len(), 2, "{line}");
+            for time in times {
+                let Some((minutes, seconds)) =
+                    time.strip_suffix('s').and_then(|t| t.split_once('m'))
+                else {
+                    panic!("{time} is not in the long format");
+                };
+                assert!(minutes.parse::<u64>().is_ok(), "{time}");
+                assert!(seconds.parse::<f64>().is_ok(), "{time}");
+                assert_eq!(
+                    seconds.split_once('.').map(|(_, millis)| millis.len()),
+                    Some(3)
+                );
+            }
+        }
+    }
+}
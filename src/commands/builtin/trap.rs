@@ -0,0 +1,72 @@
+use std::fmt::Write as _;
+
+use crate::{
+    commands::{builtin::kill::list_signals, error::CommandsError},
+    shell::{Shell, ShellError, Trap},
+};
+
+/// Set, reset or list the actions run on signals and on the pseudo-signals `EXIT`, `DEBUG`, `ERR` and `RETURN`
+pub(crate) fn invoke_trap(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let operands = match args {
+        [] => return Ok(Some(list_traps(&[], shell))),
+        [flag, specs @ ..] if flag == "-p" => return Ok(Some(list_traps(specs, shell))),
+        [flag] if flag == "-l" => return list_signals(&[]).map(Some),
+        [flag, operands @ ..] if flag == "--" => operands,
+        [flag, ..] if flag.len() > 1 && flag.starts_with('-') => {
+            return Err(CommandsError::InvalidOption("trap", flag.clone()));
+        }
+        _ => args,
+    };
+    shell.last_status = 0;
+    match operands {
+        [] => Ok(Some(list_traps(&[], shell))),
+        [action, specs @ ..] if action == "-" => {
+            for_each_trap(specs, shell, Shell::reset_trap);
+            Ok(None)
+        }
+        // without an action, or with a signal number in its place, the conditions are reset as well
+        [action, ..] if operands.len() == 1 || action.parse::<u32>().is_ok() => {
+            for_each_trap(operands, shell, Shell::reset_trap);
+            Ok(None)
+        }
+        [action, specs @ ..] => {
+            for_each_trap(specs, shell, |shell, trap| {
+                shell.set_trap(trap, action.clone());
+            });
+            Ok(None)
+        }
+    }
+}
+
+/// Apply `f` to the condition named by each of `specs`, reporting the invalid ones
+fn for_each_trap(specs: &[String], shell: &mut Shell, mut f: impl FnMut(&mut Shell, Trap)) {
+    for spec in specs {
+        if let Some(trap) = Trap::parse(spec) {
+            f(shell, trap);
+        } else {
+            let err = CommandsError::InvalidSignal("trap", spec.clone());
+            eprintln!("{}{}", shell.error_prefix(), ShellError::from(err));
+            shell.last_status = 1;
+        }
+    }
+}
+
+/// The traps on the conditions named by `specs`, or all of them, as commands that set them again
+fn list_traps(specs: &[String], shell: &mut Shell) -> String {
+    let mut traps = vec![];
+    if specs.is_empty() {
+        traps.extend(shell.traps.keys().copied());
+    } else {
+        for_each_trap(specs, shell, |_, trap| traps.push(trap));
+    }
+    let mut out = String::new();
+    for trap in traps {
+        if let Some(action) = shell.traps.get(&trap) {
+            let _ = writeln!(out, "trap -- '{}' {trap}", action.replace('\'', "'\\''"));
+        }
+    }
+    out
+}
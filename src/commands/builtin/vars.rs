@@ -0,0 +1,415 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    commands::error::CommandsError,
+    parser::{AssignValue, parse_assignment, quote},
+    shell::{
+        Shell, ShellError,
+        vars::{
+            AssignedValue, Attributes, Value, Variable, declaration, format_value, is_valid_name,
+        },
+    },
+};
+
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct DeclareOptions {
+    print: bool,
+    functions: bool,
+    function_names: bool,
+    global: bool,
+    /// `-a`, the variables are indexed arrays
+    indexed: bool,
+    /// `-A`, the variables are associative arrays
+    assoc: bool,
+    add: Attributes,
+    remove: Attributes,
+}
+
+fn parse_declare_options<'a>(
+    builtin: &'static str,
+    args: &'a [String],
+) -> Result<(DeclareOptions, &'a [String]), CommandsError> {
+    let mut opts = DeclareOptions::default();
+    let mut rest = args;
+    while let Some(arg) = rest.first() {
+        if arg == "--" {
+            rest = &rest[1..];
+            break;
+        }
+        let Some(flags) = arg.strip_prefix(['-', '+']).filter(|f| !f.is_empty()) else {
+            break;
+        };
+        let attrs = if arg.starts_with('-') {
+            &mut opts.add
+        } else {
+            &mut opts.remove
+        };
+        for flag in flags.chars() {
+            match flag {
+                'p' => opts.print = true,
+                'f' => opts.functions = true,
+                'F' => opts.function_names = true,
+                'g' => opts.global = true,
+                'a' => opts.indexed = true,
+                'A' => opts.assoc = true,
+                'r' => attrs.readonly = true,
+                'x' => attrs.exported = true,
+                'i' => attrs.integer = true,
+                'l' => attrs.lowercase = true,
+                'u' => attrs.uppercase = true,
+                _ => return Err(CommandsError::InvalidOption(builtin, format!("-{flag}"))),
+            }
+        }
+        rest = &rest[1..];
+    }
+    Ok((opts, rest))
+}
+
+pub(crate) fn invoke_declare(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    // inside a function `declare` creates local variables unless `-g` is given
+    let local = shell.call_depth > 0;
+    declare("declare", args, shell, local)
+}
+
+pub(crate) fn invoke_local(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    if shell.call_depth == 0 {
+        return Err(CommandsError::LocalOutsideFunction.into());
+    }
+    declare("local", args, shell, true)
+}
+
+pub(crate) fn invoke_readonly(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let (mut opts, names) = parse_declare_options("readonly", args)?;
+    opts.add.readonly = true;
+    if names.is_empty() {
+        return Ok(list_variables(
+            shell,
+            |var| var.attrs.contains(opts.add),
+            true,
+        ));
+    }
+    for arg in names {
+        declare_one("readonly", arg, &opts, false, shell)?;
+    }
+    Ok(None)
+}
+
+pub(crate) fn invoke_export(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let mut opts = DeclareOptions::default();
+    let mut names = args;
+    while let Some(flags) = names.first().and_then(|arg| arg.strip_prefix('-')) {
+        names = &names[1..];
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'n' => opts.remove.exported = true,
+                'p' => opts.print = true,
+                _ => return Err(CommandsError::InvalidOption("export", format!("-{flag}")))?,
+            }
+        }
+    }
+    if names.is_empty() {
+        return Ok(list_variables(shell, |var| var.attrs.exported, true));
+    }
+    if !opts.remove.exported {
+        opts.add.exported = true;
+    }
+    // `export` in a function changes the visible variable or creates a global one
+    for arg in names {
+        declare_one("export", arg, &opts, false, shell)?;
+    }
+    Ok(None)
+}
+
+pub(crate) fn invoke_printenv(args: &[String], shell: &mut Shell) -> Option<String> {
+    let env = shell.vars.exported();
+    let mut buf = String::new();
+    if args.is_empty() {
+        for (name, value) in env {
+            let _ = writeln!(buf, "{name}={value}");
+        }
+    } else {
+        let mut missing = false;
+        for name in args {
+            match env.iter().find(|(env_name, _)| env_name == name) {
+                Some((_, value)) => {
+                    let _ = writeln!(buf, "{value}");
+                }
+                None => missing = true,
+            }
+        }
+        if missing {
+            shell.last_status = 1;
+        }
+    }
+    if buf.is_empty() { None } else { Some(buf) }
+}
+
+fn declare(
+    builtin: &'static str,
+    args: &[String],
+    shell: &mut Shell,
+    local: bool,
+) -> Result<Option<String>, ShellError> {
+    let (opts, names) = parse_declare_options(builtin, args)?;
+    if opts.functions || opts.function_names {
+        return Ok(print_functions(&opts, names, shell));
+    }
+    if names.is_empty() {
+        let declarations =
+            opts.print || opts.indexed || opts.assoc || opts.add != Attributes::default();
+        let filter = |var: &Variable| {
+            var.attrs.contains(opts.add)
+                && (!opts.indexed || matches!(var.value, Some(Value::Indexed(_))))
+                && (!opts.assoc || matches!(var.value, Some(Value::Assoc(_))))
+        };
+        return Ok(list_variables(shell, filter, declarations));
+    }
+    if opts.print {
+        let mut buf = String::new();
+        for name in names {
+            if let Some(var) = shell.vars.get(name) {
+                let _ = writeln!(buf, "{}", declaration(name, var));
+            } else {
+                eprintln!("{builtin}: {name}: not found");
+                shell.last_status = 1;
+            }
+        }
+        return Ok(if buf.is_empty() { None } else { Some(buf) });
+    }
+
+    let local = local && !opts.global;
+    for arg in names {
+        declare_one(builtin, arg, &opts, local, shell)?;
+    }
+    Ok(None)
+}
+
+/// Turn the value of `name` into the kind of array asked for by `-a` or `-A`, leaving it untouched when it cannot be
+/// converted
+fn convert_value(
+    name: &str,
+    value: &mut Option<Value>,
+    opts: &DeclareOptions,
+) -> Result<(), ShellError> {
+    match value {
+        Some(Value::Assoc(_)) if opts.indexed => Err(ShellError::ArrayConversion(
+            name.to_string(),
+            "associative to indexed array",
+        )),
+        Some(Value::Scalar(_) | Value::Indexed(_)) if opts.assoc => Err(
+            ShellError::ArrayConversion(name.to_string(), "indexed to associative array"),
+        ),
+        Some(Value::Scalar(scalar)) if opts.indexed => {
+            let scalar = std::mem::take(scalar);
+            *value = Some(Value::Indexed(BTreeMap::from([(0, scalar)])));
+            Ok(())
+        }
+        None if opts.indexed => {
+            *value = Some(Value::Indexed(BTreeMap::new()));
+            Ok(())
+        }
+        None if opts.assoc => {
+            *value = Some(Value::Assoc(BTreeMap::new()));
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn declare_one(
+    builtin: &'static str,
+    arg: &str,
+    opts: &DeclareOptions,
+    local: bool,
+    shell: &mut Shell,
+) -> Result<(), ShellError> {
+    // the argument was already expanded, only the elements of a compound value are still quoted
+    let assignment = parse_assignment(arg);
+    let name = assignment
+        .as_ref()
+        .map_or(arg, |assignment| &assignment.name);
+    if !is_valid_name(name) {
+        return Err(ShellError::InvalidIdentifier(builtin, arg.to_string()));
+    }
+
+    let var = shell.vars.declare(name, local);
+    if var.attrs.readonly && (assignment.is_some() || opts.remove.readonly) {
+        return Err(ShellError::ReadonlyVariable(name.to_string()));
+    }
+    convert_value(name, &mut var.value, opts)?;
+    let attrs = &mut var.attrs;
+    attrs.exported = (attrs.exported || opts.add.exported) && !opts.remove.exported;
+    attrs.integer = (attrs.integer || opts.add.integer) && !opts.remove.integer;
+    // converting to lowercase and to uppercase exclude each other
+    if opts.add.lowercase {
+        attrs.uppercase = false;
+    }
+    if opts.add.uppercase {
+        attrs.lowercase = false;
+    }
+    attrs.lowercase = (attrs.lowercase || opts.add.lowercase) && !opts.remove.lowercase;
+    attrs.uppercase = (attrs.uppercase || opts.add.uppercase) && !opts.remove.uppercase;
+
+    // the value is assigned before making the variable readonly so `readonly x=1` works
+    if let Some(assignment) = &assignment {
+        let value = match &assignment.value {
+            AssignValue::Scalar(value) => AssignedValue::Scalar(value.clone()),
+            AssignValue::Array(elements) => {
+                AssignedValue::Array(shell.expand_array_elements(elements)?)
+            }
+        };
+        shell
+            .vars
+            .assign(name, assignment.index.as_deref(), value, assignment.append)?;
+    }
+    if opts.add.readonly {
+        shell.vars.declare(name, local).attrs.readonly = true;
+    }
+    Ok(())
+}
+
+fn print_functions(opts: &DeclareOptions, names: &[String], shell: &Shell) -> Option<String> {
+    let mut functions: Vec<_> = shell.functions.values().collect();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut buf = String::new();
+    for function in functions {
+        if !names.is_empty() && !names.contains(&function.name) {
+            continue;
+        }
+        if opts.function_names {
+            let _ = writeln!(buf, "declare -f {}", function.name);
+        } else {
+            let _ = writeln!(buf, "{function}");
+        }
+    }
+    if buf.is_empty() { None } else { Some(buf) }
+}
+
+/// List the variables matching `filter`, either as `declare` commands or as `name=value` lines
+pub(crate) fn list_variables(
+    shell: &Shell,
+    filter: impl Fn(&Variable) -> bool,
+    declarations: bool,
+) -> Option<String> {
+    let mut buf = String::new();
+    for (name, var) in shell.vars.visible() {
+        if !filter(var) {
+            continue;
+        }
+        if declarations {
+            let _ = writeln!(buf, "{}", declaration(name, var));
+        } else if let Some(value) = &var.value {
+            let value = match value {
+                Value::Scalar(value) => quote(value),
+                array => format_value(array),
+            };
+            let _ = writeln!(buf, "{name}={value}");
+        }
+    }
+    if buf.is_empty() { None } else { Some(buf) }
+}
+
+pub(crate) fn invoke_unset(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, ShellError> {
+    let mut functions_only = false;
+    let mut vars_only = false;
+    let mut names = args;
+    while let Some(flags) = names.first().and_then(|arg| arg.strip_prefix('-')) {
+        names = &names[1..];
+        if flags == "-" {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'f' => functions_only = true,
+                'v' => vars_only = true,
+                _ => return Err(CommandsError::InvalidOption("unset", format!("-{flag}")))?,
+            }
+        }
+    }
+
+    for name in names {
+        if functions_only {
+            shell.functions.remove(name);
+            continue;
+        }
+        // `unset 'arr[key]'` removes a single element
+        if let Some((array, key)) = name
+            .strip_suffix(']')
+            .and_then(|name| name.split_once('['))
+            .filter(|(array, _)| is_valid_name(array))
+        {
+            shell.vars.unset_element(array, key)?;
+            continue;
+        }
+        if !is_valid_name(name) {
+            return Err(ShellError::InvalidIdentifier("unset", name.clone()));
+        }
+        // without flags a function is only unset when there is no variable with that name
+        if vars_only || shell.vars.get(name).is_some() {
+            shell.vars.unset(name)?;
+        } else {
+            shell.functions.remove(name);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declare(args: &str, shell: &mut Shell) {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        let _ = invoke_declare(&args, shell);
+    }
+
+    #[test]
+    fn failed_array_conversion_keeps_value() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        shell.run_string("declare -A m; m[k]=v; a=(1 2 3)");
+        let before = |shell: &Shell, name| shell.vars.get(name).cloned();
+        let (m, a) = (before(&shell, "m"), before(&shell, "a"));
+        declare("-a m", &mut shell);
+        declare("-A a", &mut shell);
+        assert_eq!(shell.vars.get("m").cloned(), m);
+        assert_eq!(shell.vars.get("a").cloned(), a);
+        assert!(matches!(
+            shell.vars.get("m").and_then(|var| var.value.as_ref()),
+            Some(Value::Assoc(elements)) if elements.get("k").map(String::as_str) == Some("v")
+        ));
+    }
+
+    #[test]
+    fn scalar_converts_to_indexed_array() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        shell.run_string("s=one");
+        declare("-a s", &mut shell);
+        assert!(matches!(
+            shell.vars.get("s").and_then(|var| var.value.as_ref()),
+            Some(Value::Indexed(elements)) if elements.get(&0).map(String::as_str) == Some("one")
+        ));
+    }
+}
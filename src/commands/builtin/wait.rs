@@ -0,0 +1,130 @@
+use nix::{errno::Errno, unistd::Pid};
+
+use crate::{
+    commands::{builtin::jobs::find_job, error::CommandsError},
+    shell::{JobState, Shell, ShellError, interrupted, pending_signal},
+};
+
+/// Wait for jobs to finish, setting `$?` to the status of the last one waited for
+/// `ids` are job specs or process ids, without any every job is waited for and `$?` is 0,
+/// `-n` returns as soon as one of them finishes, with its status
+pub(crate) fn invoke_wait(
+    args: &[String],
+    shell: &mut Shell,
+) -> Result<Option<String>, CommandsError> {
+    let (any, ids) = match args.split_first() {
+        Some((flag, ids)) if flag == "-n" => (true, ids),
+        Some((flag, ids)) if flag == "--" => (false, ids),
+        Some((flag, _)) if flag.starts_with('-') && flag != "-" => {
+            return Err(CommandsError::InvalidOption("wait", flag.clone()));
+        }
+        _ => (false, args),
+    };
+
+    shell.jobs.update();
+    // ids that name no job report 127, like bash
+    let mut targets = vec![];
+    for id in ids {
+        match resolve(id, shell) {
+            Ok(job) => targets.push(Some(job)),
+            Err(e) => {
+                eprintln!("{}{}", shell.error_prefix(), ShellError::from(e));
+                targets.push(None);
+            }
+        }
+    }
+    let waited_for: Vec<usize> = if ids.is_empty() {
+        shell.jobs.iter().map(|job| job.id).collect()
+    } else {
+        targets.iter().flatten().copied().collect()
+    };
+
+    shell.last_status = if any {
+        wait_any(&waited_for, shell)
+    } else {
+        wait_all(&waited_for, shell);
+        match targets.last() {
+            Some(Some(id)) => shell.jobs.get(*id).map_or(127, |job| job.state.status()),
+            Some(None) => 127,
+            None => 0,
+        }
+    };
+    // a signal with a trap ends the wait, its trap runs right after
+    if interrupted() {
+        shell.last_status = 130;
+    } else if let Some(signal) = pending_signal() {
+        shell.last_status = 128 + signal as i32;
+    }
+    // a job whose status `wait` reported is not reported again
+    for id in waited_for {
+        if shell
+            .jobs
+            .get(id)
+            .is_some_and(|job| job.state.is_finished())
+        {
+            shell.jobs.remove(id);
+        }
+    }
+    Ok(None)
+}
+
+/// The job a job spec or process id given to `wait` names
+fn resolve(id: &str, shell: &mut Shell) -> Result<usize, CommandsError> {
+    if id.starts_with('%') {
+        return find_job("wait", Some(id), &shell.jobs);
+    }
+    let pid = id
+        .parse()
+        .map_err(|_| CommandsError::NotAChild("wait", id.into()))?;
+    shell
+        .jobs
+        .find_process(Pid::from_raw(pid))
+        .map(|job| job.id)
+        .ok_or_else(|| CommandsError::NotAChild("wait", id.into()))
+}
+
+/// Wait until none of the jobs `ids` is running any more
+fn wait_all(ids: &[usize], shell: &mut Shell) {
+    let running = |shell: &Shell| {
+        ids.iter().any(|&id| {
+            shell
+                .jobs
+                .get(id)
+                .is_some_and(|job| job.state == JobState::Running)
+        })
+    };
+    while running(shell) {
+        if !wait_for_child(shell) {
+            return;
+        }
+    }
+}
+
+/// Wait until one of the jobs `ids` has finished and return its status, 127 when there is none to wait for
+fn wait_any(ids: &[usize], shell: &mut Shell) -> i32 {
+    loop {
+        let jobs = ids.iter().filter_map(|&id| shell.jobs.get(id));
+        if let Some(job) = jobs.clone().find(|job| job.state.is_finished()) {
+            return job.state.status();
+        }
+        if !jobs.clone().any(|job| job.state == JobState::Running) {
+            return 127;
+        }
+        if !wait_for_child(shell) {
+            return 130;
+        }
+    }
+}
+
+/// Block until a child changes state, returns false when Ctrl-C or a signal with a trap interrupted the wait
+fn wait_for_child(shell: &mut Shell) -> bool {
+    match shell.jobs.wait_any() {
+        Err(Errno::EINTR) => !interrupted() && pending_signal().is_none(),
+        Ok(()) => true,
+        Err(_) => {
+            // no children are left, the jobs are marked as done without a status
+            shell.jobs.update();
+            true
+        }
+    }
+}
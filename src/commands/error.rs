@@ -10,4 +10,34 @@ pub enum CommandsError {
     HomeNotSet,
     #[error("Could not obtain current directory")]
     InvalidCurrentDirectory(#[from] io::Error),
+    #[error("{0}: {1}: numeric argument required")]
+    NumericArgumentRequired(&'static str, String),
+    #[error("return: can only `return' from a function")]
+    ReturnOutsideFunction,
+    #[error("local: can only be used in a function")]
+    LocalOutsideFunction,
+    #[error("source: filename argument required")]
+    FilenameRequired,
+    #[error("{0}: {1}: invalid option")]
+    InvalidOption(&'static str, String),
+    #[error("{0}: {1}: no such job")]
+    NoSuchJob(&'static str, String),
+    #[error("{0}: {1}: ambiguous job spec")]
+    AmbiguousJob(&'static str, String),
+    #[error("{0}: job has terminated")]
+    JobTerminated(&'static str),
+    #[error("{0}: pid {1} is not a child of this shell")]
+    NotAChild(&'static str, String),
+    #[error("{0}: {1}: invalid signal specification")]
+    InvalidSignal(&'static str, String),
+    #[error("{0}: {1}: arguments must be process or job IDs")]
+    NotAPidOrJob(&'static str, String),
+    #[error("{0}: ({1}) - {2}")]
+    SignalFailed(&'static str, String, &'static str),
+    #[error("{0}: usage: {1}")]
+    Usage(&'static str, &'static str),
+    #[error("{0}: no job control")]
+    NoJobControl(&'static str),
+    #[error("{0}: {1}: invalid option name")]
+    InvalidOptionName(&'static str, String),
 }
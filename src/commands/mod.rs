@@ -5,4 +5,4 @@ mod resolve;
 pub use builtin::BUILTIN_COMMANDS;
 pub use builtin::Builtin;
 pub(crate) use builtin::invoke_builtin;
-pub(crate) use resolve::find_exec_file;
+pub(crate) use resolve::{find_exec_file, find_file};
@@ -3,11 +3,15 @@ use std::path::PathBuf;
 
 use crate::commands::error::CommandsError;
 
-pub(crate) fn find_exec_file(cmd: &str) -> Result<Option<PathBuf>, CommandsError> {
-    let Some(env_path) = std::env::var_os("PATH") else {
+/// Find the executable `cmd` in the directories of `search_path`, the value of the shell's `PATH` variable
+pub(crate) fn find_exec_file(
+    cmd: &str,
+    search_path: Option<&str>,
+) -> Result<Option<PathBuf>, CommandsError> {
+    let Some(search_path) = search_path else {
         return Err(CommandsError::PathNotSet)?;
     };
-    for mut path in std::env::split_paths(&env_path) {
+    for mut path in std::env::split_paths(search_path) {
         if let Ok(exists) = path.try_exists() {
             if !exists {
                 continue;
@@ -20,3 +24,10 @@ pub(crate) fn find_exec_file(cmd: &str) -> Result<Option<PathBuf>, CommandsError
     }
     Ok(None)
 }
+
+/// Find a readable file named `name` in the directories of `search_path`, as done by `source`
+pub(crate) fn find_file(name: &str, search_path: Option<&str>) -> Option<PathBuf> {
+    std::env::split_paths(search_path?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file() && path.readable())
+}
@@ -11,4 +11,4 @@ pub type Result<T> = std::result::Result<T, ClawshError>;
 pub use crate::commands::BUILTIN_COMMANDS;
 pub use crate::completion::TrieCompleter;
 use crate::shell::ClawshError;
-pub use crate::shell::Shell;
+pub use crate::shell::{Input, RcFile, Shell, ShellOption, StartupOptions};
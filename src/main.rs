@@ -1,9 +1,235 @@
 //! Entrypoint for the clawsh shell.
-use clawsh::Shell;
+use std::{
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+use clawsh::{Input, RcFile, Shell, ShellOption, StartupOptions};
+
+const USAGE: &str = "\
+Usage: clawsh [option ...] [-c command [name [arg ...]] | [-s] [script [arg ...]]]
+
+Options:
+  -c              read commands from the first argument instead of a script
+  -i              force the shell to be interactive
+  -l, --login     act as a login shell
+  -s              read commands from stdin, all arguments become positional parameters
+  -n              read commands without executing them
+  -e, -u, -x      turn on errexit, nounset or xtrace, `+e` etc. turn them off
+  -o option       turn on an option by name, `+o option` turns it off
+  --norc          do not read the interactive startup file
+  --rcfile FILE   read FILE instead of the interactive startup file
+  --noprofile     do not read the login startup files
+  --version       print the version and exit
+  --help          print this help and exit";
+
+/// Command line of the shell, options are only recognised before the first operand like in `sh`
+#[derive(Default)]
+struct Args {
+    startup: StartupOptions,
+    force_interactive: bool,
+    command: bool,
+    stdin: bool,
+    options: Vec<(ShellOption, bool)>,
+    operands: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" | "-" => break,
+            "--login" => parsed.startup.login = true,
+            "--norc" => parsed.startup.rc = RcFile::None,
+            "--noprofile" => parsed.startup.noprofile = true,
+            "--rcfile" => {
+                let file = args.next().ok_or("--rcfile: option requires an argument")?;
+                parsed.startup.rc = RcFile::File(PathBuf::from(file));
+            }
+            "--version" => {
+                println!("clawsh {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(format!("{arg}: invalid option")),
+            _ if arg.len() > 1 && arg.starts_with(['-', '+']) => {
+                let enable = arg.starts_with('-');
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'c' if enable => parsed.command = true,
+                        'i' if enable => parsed.force_interactive = true,
+                        'l' if enable => parsed.startup.login = true,
+                        's' if enable => parsed.stdin = true,
+                        'o' => {
+                            let name = args.next().ok_or("-o: option requires an argument")?;
+                            let option = ShellOption::from_name(&name)
+                                .ok_or_else(|| format!("{name}: invalid option name"))?;
+                            parsed.options.push((option, enable));
+                        }
+                        _ => {
+                            let option = ShellOption::from_flag(flag)
+                                .ok_or_else(|| format!("{}{flag}: invalid option", &arg[..1]))?;
+                            parsed.options.push((option, enable));
+                        }
+                    }
+                }
+            }
+            _ => {
+                parsed.operands.push(arg);
+                break;
+            }
+        }
+    }
+    parsed.operands.extend(args);
+    Ok(parsed)
+}
 
 fn main() -> clawsh::Result<()> {
-    let mut shell = Shell::setup()?;
-    shell.run();
+    let mut args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("clawsh: {msg}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let mut operands = std::mem::take(&mut args.operands).into_iter();
+    // without a command string or script the shell reads stdin, interactively when it is a terminal
+    let reads_stdin = !args.command && (args.stdin || operands.len() == 0);
+    args.startup.interactive = args.force_interactive || (reads_stdin && io::stdin().is_terminal());
+    args.startup.input = if args.command {
+        Input::Command
+    } else if reads_stdin {
+        Input::Stdin
+    } else {
+        Input::Script
+    };
+
+    let mut shell = Shell::setup_with(&args.startup)?;
+    for (option, enable) in args.options {
+        shell.set_option(option, enable);
+    }
+
+    if args.command {
+        let Some(command) = operands.next() else {
+            eprintln!("clawsh: -c: option requires an argument");
+            std::process::exit(2);
+        };
+        // like `sh -c cmd name args...`, the first argument after the command is `$0`
+        if let Some(name) = operands.next() {
+            shell.set_arg0(name);
+        }
+        shell.set_positional_parameters(operands.collect());
+        shell.run_string(&command);
+    } else if reads_stdin {
+        shell.set_positional_parameters(operands.collect());
+        if args.startup.interactive {
+            shell.run();
+        } else {
+            shell.run_reader(io::stdin().lock());
+        }
+    } else if let Some(script) = operands.next() {
+        shell.set_positional_parameters(operands.collect());
+        shell.run_script(Path::new(&script));
+    }
+
+    // the `EXIT` trap runs first so that it can still change the status with `exit`
+    shell.run_exit_trap();
+    let status = shell.last_status();
     shell.exit()?;
-    Ok(())
+    std::process::exit(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn combined_flags() {
+        let Ok(args) = parse(&["-ilex", "+u", "script", "-e"]) else {
+            panic!("flags should parse");
+        };
+        assert!(args.force_interactive && args.startup.login && !args.command);
+        assert_eq!(
+            args.options,
+            [
+                (ShellOption::Errexit, true),
+                (ShellOption::Xtrace, true),
+                (ShellOption::Nounset, false),
+            ]
+        );
+        // options end at the first operand
+        assert_eq!(args.operands, ["script", "-e"]);
+    }
+
+    #[test]
+    fn named_options() {
+        let Ok(args) = parse(&["-o", "errexit", "+o", "xtrace", "-so", "nounset"]) else {
+            panic!("named options should parse");
+        };
+        assert!(args.stdin);
+        assert_eq!(
+            args.options,
+            [
+                (ShellOption::Errexit, true),
+                (ShellOption::Xtrace, false),
+                (ShellOption::Nounset, true),
+            ]
+        );
+        assert_eq!(
+            parse(&["-o", "nope"]).err(),
+            Some("nope: invalid option name".into())
+        );
+        assert_eq!(
+            parse(&["-o"]).err(),
+            Some("-o: option requires an argument".into())
+        );
+    }
+
+    #[test]
+    fn startup_options() {
+        let Ok(args) = parse(&["--login", "--noprofile", "--rcfile", "rc"]) else {
+            panic!("long options should parse");
+        };
+        assert!(args.startup.login && args.startup.noprofile);
+        assert_eq!(args.startup.rc, RcFile::File("rc".into()));
+        let Ok(args) = parse(&["--rcfile", "rc", "--norc"]) else {
+            panic!("long options should parse");
+        };
+        assert_eq!(args.startup.rc, RcFile::None);
+        assert_eq!(
+            parse(&["--rcfile"]).err(),
+            Some("--rcfile: option requires an argument".into())
+        );
+    }
+
+    #[test]
+    fn command_string_operands() {
+        let Ok(args) = parse(&["-c", "echo $0 $1", "name", "-x", "arg"]) else {
+            panic!("-c should parse");
+        };
+        assert!(args.command && args.options.is_empty());
+        assert_eq!(args.operands, ["echo $0 $1", "name", "-x", "arg"]);
+        let Ok(args) = parse(&["-e", "--", "-c"]) else {
+            panic!("-- should end the options");
+        };
+        assert!(!args.command);
+        assert_eq!(args.operands, ["-c"]);
+    }
+
+    #[test]
+    fn unknown_options() {
+        assert_eq!(parse(&["-q"]).err(), Some("-q: invalid option".into()));
+        assert_eq!(parse(&["+eq"]).err(), Some("+q: invalid option".into()));
+        assert_eq!(
+            parse(&["--posix"]).err(),
+            Some("--posix: invalid option".into())
+        );
+    }
 }
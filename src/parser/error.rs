@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("syntax error: unexpected end of file")]
+    Incomplete,
+    /// A token that is not allowed where it appears, along with the line it is on
+    #[error("syntax error near unexpected token `{0}'")]
+    UnexpectedToken(String, usize),
+}
@@ -1,6 +1,12 @@
+pub(crate) mod error;
+mod script;
 mod token;
 mod words;
 
+pub(crate) use script::{Command, Function, parse_script, parse_script_at};
 pub use token::Token;
 pub use token::tokenize_input;
-pub(crate) use words::split_words;
+pub(crate) use words::{
+    AssignValue, Assignment, Param, WordPart, parse_assignment, parse_word, quote,
+    split_keyed_element, split_words,
+};
@@ -0,0 +1,791 @@
+use std::{fmt, iter::Peekable, rc::Rc, str::Chars, vec};
+
+use crate::parser::error::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single command of a parsed script
+pub(crate) enum Command {
+    /// A simple command or pipeline, kept as raw words with their quoting intact so it can be expanded right before it runs
+    Simple {
+        words: Vec<String>,
+        /// line of the source the command starts on, counting from 1
+        line: usize,
+    },
+    /// A `{ ...; }` group of commands executed in the current shell
+    Group(Vec<Command>),
+    /// A function definition, either `name() { ... }` or `function name { ... }`
+    FunctionDef(Rc<Function>),
+    /// A `for name [in words]; do ...; done` loop, iterating over the positional parameters when `words` is `None`
+    For {
+        var: String,
+        words: Option<Vec<String>>,
+        body: Vec<Command>,
+    },
+    /// A command preceded by the `time` reserved word, `-p` selects the POSIX output format
+    Time {
+        posix: bool,
+        command: Option<Box<Command>>,
+    },
+    /// A command terminated by `&`, run asynchronously as a job
+    Background(Box<Command>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A shell function, its body is shared between the definition and the function table of the shell
+pub(crate) struct Function {
+    pub(crate) name: String,
+    pub(crate) body: Vec<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Lexeme {
+    Word(String),
+    Semi,
+    Amp,
+    Newline,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lexeme::Word(word) => write!(f, "{word}"),
+            Lexeme::Semi => write!(f, ";"),
+            Lexeme::Amp => write!(f, "&"),
+            Lexeme::Newline => write!(f, "newline"),
+            Lexeme::LParen => write!(f, "("),
+            Lexeme::RParen => write!(f, ")"),
+        }
+    }
+}
+
+/// Parse a complete script (one or more lines) into a list of commands
+///
+/// Returns `ParseError::Incomplete` when the input ends inside a quote, a group or a function body,
+/// which the REPL uses to ask for a continuation line
+pub(crate) fn parse_script(src: &str) -> Result<Vec<Command>, ParseError> {
+    parse_script_at(src, 1)
+}
+
+/// Parse a script whose first line is line `first_line` of a larger source, e.g. the next command of a file
+pub(crate) fn parse_script_at(src: &str, first_line: usize) -> Result<Vec<Command>, ParseError> {
+    let mut parser = Parser {
+        lexemes: lex(src, first_line)?.into_iter().peekable(),
+        line: first_line,
+    };
+    parser.parse_list(None)
+}
+
+/// Split source into raw words the way the script parser does, e.g. the elements of a compound array assignment
+pub(crate) fn split_raw_words(src: &str) -> Result<Vec<String>, ParseError> {
+    lex(src, 1)?
+        .into_iter()
+        .filter(|(lexeme, _)| *lexeme != Lexeme::Newline)
+        .map(|(lexeme, line)| match lexeme {
+            Lexeme::Word(word) => Ok(word),
+            lexeme => Err(ParseError::UnexpectedToken(lexeme.to_string(), line)),
+        })
+        .collect()
+}
+
+/// Characters of the source being lexed, keeping track of the line they are on
+struct Source<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Iterator for Source<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+}
+
+/// Split `src` into lexemes, each paired with the line it starts on counting from `first_line`
+fn lex(src: &str, first_line: usize) -> Result<Vec<(Lexeme, usize)>, ParseError> {
+    let mut lexemes = vec![];
+    let mut buf = String::new();
+    let mut chars = Source {
+        chars: src.chars().peekable(),
+        line: first_line,
+    };
+    let mut word_line = first_line;
+    loop {
+        let line = chars.line;
+        let Some(c) = chars.next() else {
+            break;
+        };
+        if buf.is_empty() {
+            word_line = line;
+        }
+        match c {
+            ' ' | '\t' => flush_word(&mut buf, word_line, &mut lexemes),
+            '(' if is_assignment_prefix(&buf) => {
+                // the parenthesised value of a compound array assignment `name=(a b c)` stays part of the word
+                buf.push(c);
+                read_compound_value(&mut chars, &mut buf)?;
+            }
+            '\n' | ';' | '&' | '(' | ')' => {
+                flush_word(&mut buf, word_line, &mut lexemes);
+                let lexeme = match c {
+                    '\n' => Lexeme::Newline,
+                    ';' => Lexeme::Semi,
+                    '&' => Lexeme::Amp,
+                    '(' => Lexeme::LParen,
+                    _ => Lexeme::RParen,
+                };
+                lexemes.push((lexeme, line));
+            }
+            '#' if buf.is_empty() => {
+                // comments run until the end of the line, the newline itself is still a separator
+                while chars.chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '\'' => read_single_quoted(&mut chars, &mut buf)?,
+            '\"' => read_double_quoted(&mut chars, &mut buf)?,
+            '`' => read_backquoted(&mut chars, &mut buf)?,
+            '$' if chars.chars.next_if_eq(&'(').is_some() => {
+                buf.push_str("$(");
+                read_command_subst(&mut chars, &mut buf)?;
+            }
+            '\\' => match chars.next() {
+                // line continuation
+                Some('\n') => {}
+                Some(next_char) => {
+                    buf.push(c);
+                    buf.push(next_char);
+                }
+                None => return Err(ParseError::Incomplete),
+            },
+            _ => buf.push(c),
+        }
+    }
+    flush_word(&mut buf, word_line, &mut lexemes);
+    Ok(lexemes)
+}
+
+fn read_single_quoted(
+    chars: &mut impl Iterator<Item = char>,
+    buf: &mut String,
+) -> Result<(), ParseError> {
+    buf.push('\'');
+    loop {
+        match chars.next() {
+            Some('\'') => break,
+            Some(c) => buf.push(c),
+            None => return Err(ParseError::Incomplete),
+        }
+    }
+    buf.push('\'');
+    Ok(())
+}
+
+fn read_double_quoted(
+    chars: &mut impl Iterator<Item = char>,
+    buf: &mut String,
+) -> Result<(), ParseError> {
+    buf.push('\"');
+    let mut prev_dollar = false;
+    loop {
+        match chars.next() {
+            Some('\"') => break,
+            Some('\\') => {
+                buf.push('\\');
+                buf.push(chars.next().ok_or(ParseError::Incomplete)?);
+            }
+            Some('`') => read_backquoted(chars, buf)?,
+            Some('(') if prev_dollar => {
+                buf.push('(');
+                read_command_subst(chars, buf)?;
+            }
+            Some(c) => {
+                buf.push(c);
+                prev_dollar = c == '$';
+                continue;
+            }
+            None => return Err(ParseError::Incomplete),
+        }
+        prev_dollar = false;
+    }
+    buf.push('\"');
+    Ok(())
+}
+
+fn read_backquoted(
+    chars: &mut impl Iterator<Item = char>,
+    buf: &mut String,
+) -> Result<(), ParseError> {
+    buf.push('`');
+    loop {
+        match chars.next() {
+            Some('`') => break,
+            Some('\\') => {
+                buf.push('\\');
+                buf.push(chars.next().ok_or(ParseError::Incomplete)?);
+            }
+            Some(c) => buf.push(c),
+            None => return Err(ParseError::Incomplete),
+        }
+    }
+    buf.push('`');
+    Ok(())
+}
+
+/// Read the command of a `$(...)` substitution after its opening `$(` up to and including the matching `)`
+pub(super) fn read_command_subst(
+    chars: &mut impl Iterator<Item = char>,
+    buf: &mut String,
+) -> Result<(), ParseError> {
+    let mut depth = 0_usize;
+    loop {
+        let c = chars.next().ok_or(ParseError::Incomplete)?;
+        match c {
+            '\'' => read_single_quoted(chars, buf)?,
+            '\"' => read_double_quoted(chars, buf)?,
+            '`' => read_backquoted(chars, buf)?,
+            '\\' => {
+                buf.push(c);
+                buf.push(chars.next().ok_or(ParseError::Incomplete)?);
+            }
+            ')' if depth == 0 => {
+                buf.push(c);
+                return Ok(());
+            }
+            _ => {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                buf.push(c);
+            }
+        }
+    }
+}
+
+/// Whether `buf` is the `name=` or `name+=` start of an assignment word
+fn is_assignment_prefix(buf: &str) -> bool {
+    let name = buf
+        .strip_suffix("+=")
+        .or_else(|| buf.strip_suffix('='))
+        .unwrap_or_default();
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn read_compound_value(chars: &mut Source<'_>, buf: &mut String) -> Result<(), ParseError> {
+    loop {
+        match chars.next() {
+            Some(')') => break,
+            Some('$') if chars.chars.next_if_eq(&'(').is_some() => {
+                buf.push_str("$(");
+                read_command_subst(chars, buf)?;
+            }
+            Some('`') => read_backquoted(chars, buf)?,
+            Some('\'') => read_single_quoted(chars, buf)?,
+            Some('\"') => read_double_quoted(chars, buf)?,
+            Some('\\') => {
+                buf.push('\\');
+                buf.push(chars.next().ok_or(ParseError::Incomplete)?);
+            }
+            // the elements may span several lines
+            Some('\n') => buf.push(' '),
+            Some(c) => buf.push(c),
+            None => return Err(ParseError::Incomplete),
+        }
+    }
+    buf.push(')');
+    Ok(())
+}
+
+fn flush_word(buf: &mut String, line: usize, lexemes: &mut Vec<(Lexeme, usize)>) {
+    if !buf.is_empty() {
+        lexemes.push((Lexeme::Word(std::mem::take(buf)), line));
+    }
+}
+
+struct Parser {
+    lexemes: Peekable<vec::IntoIter<(Lexeme, usize)>>,
+    /// line of the last lexeme consumed, reported in syntax errors
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&mut self) -> Option<&Lexeme> {
+        self.lexemes.peek().map(|(lexeme, _)| lexeme)
+    }
+
+    fn next(&mut self) -> Option<Lexeme> {
+        let (lexeme, line) = self.lexemes.next()?;
+        self.line = line;
+        Some(lexeme)
+    }
+
+    fn unexpected(&self, token: impl fmt::Display) -> ParseError {
+        ParseError::UnexpectedToken(token.to_string(), self.line)
+    }
+
+    fn next_if_eq(&mut self, expected: &Lexeme) -> Option<Lexeme> {
+        self.lexemes
+            .next_if(|(lexeme, _)| lexeme == expected)
+            .map(|(lexeme, _)| lexeme)
+    }
+
+    fn next_word(&mut self) -> Option<String> {
+        match self
+            .lexemes
+            .next_if(|(lexeme, _)| matches!(lexeme, Lexeme::Word(_)))
+        {
+            Some((Lexeme::Word(word), _)) => Some(word),
+            _ => None,
+        }
+    }
+
+    /// Parse commands until the end of input, or until the reserved word `terminator` which is consumed
+    fn parse_list(&mut self, terminator: Option<&str>) -> Result<Vec<Command>, ParseError> {
+        let mut commands = vec![];
+        loop {
+            match self.peek() {
+                None if terminator.is_some() => return Err(ParseError::Incomplete),
+                None => return Ok(commands),
+                Some(Lexeme::Semi | Lexeme::Newline) => {
+                    self.next();
+                }
+                Some(Lexeme::Word(word)) if terminator == Some(word.as_str()) => {
+                    self.next();
+                    return Ok(commands);
+                }
+                Some(_) => {
+                    let command = self.parse_command()?;
+                    // `&` separates commands like `;` but runs the one before it in the background
+                    if self.next_if_eq(&Lexeme::Amp).is_some() {
+                        commands.push(Command::Background(Box::new(command)));
+                        continue;
+                    }
+                    let is_compound = !matches!(command, Command::Simple { .. });
+                    commands.push(command);
+                    match self.peek() {
+                        None | Some(Lexeme::Semi | Lexeme::Newline) => {}
+                        // `{ { a; } }` closes both groups without a separator in between
+                        Some(Lexeme::Word(word))
+                            if is_compound && terminator == Some(word.as_str()) => {}
+                        Some(lexeme) => {
+                            let token = lexeme.to_string();
+                            self.next();
+                            return Err(self.unexpected(token));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParseError> {
+        let line = self.lexemes.peek().map_or(0, |(_, line)| *line);
+        match self.next() {
+            Some(Lexeme::Word(word)) if word == "{" => {
+                Ok(Command::Group(self.parse_list(Some("}"))?))
+            }
+            Some(Lexeme::Word(word)) if word == "for" => self.parse_for(),
+            Some(Lexeme::Word(word)) if word == "time" => self.parse_time(),
+            Some(Lexeme::Word(word)) if matches!(word.as_str(), "do" | "done") => {
+                Err(self.unexpected(word))
+            }
+            Some(Lexeme::Word(word)) if word == "function" => {
+                let name = match self.next() {
+                    Some(Lexeme::Word(name)) => name,
+                    Some(lexeme) => return Err(self.unexpected(lexeme)),
+                    None => return Err(ParseError::Incomplete),
+                };
+                if self.peek() == Some(&Lexeme::LParen) {
+                    self.parse_parens()?;
+                }
+                self.parse_function_body(name)
+            }
+            Some(Lexeme::Word(name)) if self.peek() == Some(&Lexeme::LParen) => {
+                self.parse_parens()?;
+                self.parse_function_body(name)
+            }
+            Some(Lexeme::Word(word)) => {
+                let mut words = vec![word];
+                while let Some(word) = self.next_word() {
+                    words.push(word);
+                }
+                Ok(Command::Simple { words, line })
+            }
+            Some(lexeme) => Err(self.unexpected(lexeme)),
+            None => Err(ParseError::Incomplete),
+        }
+    }
+
+    fn parse_parens(&mut self) -> Result<(), ParseError> {
+        for expected in [Lexeme::LParen, Lexeme::RParen] {
+            match self.next() {
+                Some(lexeme) if lexeme == expected => {}
+                Some(lexeme) => return Err(self.unexpected(lexeme)),
+                None => return Err(ParseError::Incomplete),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_for(&mut self) -> Result<Command, ParseError> {
+        let var = match self.next() {
+            Some(Lexeme::Word(var)) => var,
+            Some(lexeme) => return Err(self.unexpected(lexeme)),
+            None => return Err(ParseError::Incomplete),
+        };
+        while self.next_if_eq(&Lexeme::Newline).is_some() {}
+        let words = match self.peek() {
+            Some(Lexeme::Word(word)) if word == "in" => {
+                self.next();
+                let mut words = vec![];
+                while let Some(word) = self.next_word() {
+                    words.push(word);
+                }
+                match self.next() {
+                    Some(Lexeme::Semi | Lexeme::Newline) => {}
+                    Some(lexeme) => return Err(self.unexpected(lexeme)),
+                    None => return Err(ParseError::Incomplete),
+                }
+                Some(words)
+            }
+            _ => {
+                self.next_if_eq(&Lexeme::Semi);
+                None
+            }
+        };
+        while self.next_if_eq(&Lexeme::Newline).is_some() {}
+        match self.next() {
+            Some(Lexeme::Word(word)) if word == "do" => {
+                let body = self.parse_list(Some("done"))?;
+                Ok(Command::For { var, words, body })
+            }
+            Some(lexeme) => Err(self.unexpected(lexeme)),
+            None => Err(ParseError::Incomplete),
+        }
+    }
+
+    fn parse_time(&mut self) -> Result<Command, ParseError> {
+        let posix = self.next_if_eq(&Lexeme::Word("-p".into())).is_some();
+        // `time` on its own only reports the times of doing nothing
+        let command = match self.peek() {
+            None | Some(Lexeme::Semi | Lexeme::Newline | Lexeme::Amp) => None,
+            Some(_) => Some(Box::new(self.parse_command()?)),
+        };
+        Ok(Command::Time { posix, command })
+    }
+
+    fn parse_function_body(&mut self, name: String) -> Result<Command, ParseError> {
+        // the body may start on the line after the name
+        while self.next_if_eq(&Lexeme::Newline).is_some() {}
+        match self.next() {
+            Some(Lexeme::Word(word)) if word == "{" => {
+                let body = self.parse_list(Some("}"))?;
+                Ok(Command::FunctionDef(Rc::new(Function { name, body })))
+            }
+            Some(lexeme) => Err(self.unexpected(lexeme)),
+            None => Err(ParseError::Incomplete),
+        }
+    }
+}
+
+impl Command {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        self.fmt_prefixed(f, depth, "")
+    }
+
+    /// Format the command with `prefix` in front of its first line, e.g. the `time` reserved word
+    fn fmt_prefixed(&self, f: &mut fmt::Formatter<'_>, depth: usize, prefix: &str) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        let first = format!("{indent}{prefix}");
+        match self {
+            Command::Simple { words, .. } => writeln!(f, "{first}{}", words.join(" ")),
+            Command::Group(commands) => {
+                writeln!(f, "{first}{{")?;
+                for command in commands {
+                    command.fmt_indented(f, depth + 1)?;
+                }
+                writeln!(f, "{indent}}}")
+            }
+            Command::FunctionDef(function) => {
+                writeln!(f, "{first}{} ()", function.name)?;
+                writeln!(f, "{indent}{{")?;
+                for command in &function.body {
+                    command.fmt_indented(f, depth + 1)?;
+                }
+                writeln!(f, "{indent}}}")
+            }
+            Command::For { var, words, body } => {
+                match words {
+                    Some(words) => writeln!(f, "{first}for {var} in {};", words.join(" "))?,
+                    None => writeln!(f, "{first}for {var};")?,
+                }
+                writeln!(f, "{indent}do")?;
+                for command in body {
+                    command.fmt_indented(f, depth + 1)?;
+                }
+                writeln!(f, "{indent}done")
+            }
+            Command::Time { posix, command } => {
+                let time = if *posix { "time -p " } else { "time " };
+                match command {
+                    Some(command) => command.fmt_prefixed(f, depth, &format!("{prefix}{time}")),
+                    None => writeln!(f, "{first}{}", time.trim_end()),
+                }
+            }
+            Command::Background(command) => {
+                // `&` goes at the end of the last line of the command
+                let text = Indented(command, depth, prefix).to_string();
+                writeln!(f, "{} &", text.trim_end())
+            }
+        }
+    }
+}
+
+/// A command formatted over several indented lines, as in the body of a function
+struct Indented<'a>(&'a Command, usize, &'a str);
+
+impl fmt::Display for Indented<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_prefixed(f, self.1, self.2)
+    }
+}
+
+impl fmt::Display for Command {
+    /// Formats the command on a single line, as listed by `jobs`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Simple { words, .. } => write!(f, "{}", words.join(" ")),
+            Command::Group(commands) => write!(f, "{{ {} }}", List(commands)),
+            Command::FunctionDef(function) => {
+                write!(f, "{} () {{ {} }}", function.name, List(&function.body))
+            }
+            Command::For { var, words, body } => {
+                match words {
+                    Some(words) => write!(f, "for {var} in {}; ", words.join(" "))?,
+                    None => write!(f, "for {var}; ")?,
+                }
+                write!(f, "do {} done", List(body))
+            }
+            Command::Time { posix, command } => {
+                write!(f, "time")?;
+                if *posix {
+                    write!(f, " -p")?;
+                }
+                match command {
+                    Some(command) => write!(f, " {command}"),
+                    None => Ok(()),
+                }
+            }
+            Command::Background(command) => write!(f, "{command} &"),
+        }
+    }
+}
+
+/// Commands on a single line, each terminated by `;` unless it already ends in `&`
+struct List<'a>(&'a [Command]);
+
+impl fmt::Display for List<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, command) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match command {
+                Command::Background(_) => write!(f, "{command}")?,
+                _ => write!(f, "{command};")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Function {
+    /// Formats the function as a re-parseable definition, as printed by `type`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ()", self.name)?;
+        writeln!(f, "{{")?;
+        for command in &self.body {
+            command.fmt_indented(f, 1)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simple(words: &[&str], line: usize) -> Command {
+        Command::Simple {
+            words: words.iter().map(ToString::to_string).collect(),
+            line,
+        }
+    }
+
+    #[test]
+    fn parse_list() {
+        assert_eq!(
+            parse_script("echo 'a; b' ; pwd\n# comment\nls | wc -l"),
+            Ok(vec![
+                simple(&["echo", "'a; b'"], 1),
+                simple(&["pwd"], 1),
+                simple(&["ls", "|", "wc", "-l"], 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_function_definitions() {
+        let expected = |lines: [usize; 2]| {
+            Command::FunctionDef(Rc::new(Function {
+                name: "greet".into(),
+                body: vec![
+                    simple(&["echo", "hi"], lines[0]),
+                    simple(&["echo", "there"], lines[1]),
+                ],
+            }))
+        };
+        assert_eq!(
+            parse_script("greet() { echo hi; echo there; }"),
+            Ok(vec![expected([1, 1])])
+        );
+        assert_eq!(
+            parse_script("function greet {\n  echo hi\n  echo there\n}"),
+            Ok(vec![expected([2, 3])])
+        );
+    }
+
+    #[test]
+    fn parse_for_loops() {
+        let expected = |line| Command::For {
+            var: "x".into(),
+            words: Some(vec!["\"${arr[@]}\"".into(), "b".into()]),
+            body: vec![simple(&["echo", "$x"], line)],
+        };
+        assert_eq!(
+            parse_script("for x in \"${arr[@]}\" b; do echo $x; done"),
+            Ok(vec![expected(1)])
+        );
+        assert_eq!(
+            parse_script("for x in \"${arr[@]}\" b\ndo\n  echo $x\ndone"),
+            Ok(vec![expected(3)])
+        );
+        assert_eq!(parse_script("for x; do"), Err(ParseError::Incomplete));
+    }
+
+    #[test]
+    fn parse_time() {
+        assert_eq!(
+            parse_script("time -p ls | wc -l; time { pwd; }\ntime"),
+            Ok(vec![
+                Command::Time {
+                    posix: true,
+                    command: Some(Box::new(simple(&["ls", "|", "wc", "-l"], 1))),
+                },
+                Command::Time {
+                    posix: false,
+                    command: Some(Box::new(Command::Group(vec![simple(&["pwd"], 1)]))),
+                },
+                Command::Time {
+                    posix: false,
+                    command: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_background() {
+        let commands = parse_script("sleep 1 & { a; b & }&\ntime c &").unwrap_or_default();
+        assert_eq!(
+            commands,
+            vec![
+                Command::Background(Box::new(simple(&["sleep", "1"], 1))),
+                Command::Background(Box::new(Command::Group(vec![
+                    simple(&["a"], 1),
+                    Command::Background(Box::new(simple(&["b"], 1))),
+                ]))),
+                Command::Background(Box::new(Command::Time {
+                    posix: false,
+                    command: Some(Box::new(simple(&["c"], 2))),
+                })),
+            ]
+        );
+        let lines: Vec<_> = commands.iter().map(ToString::to_string).collect();
+        assert_eq!(lines, ["sleep 1 &", "{ a; b & } &", "time c &"]);
+        assert_eq!(
+            parse_script("& a"),
+            Err(ParseError::UnexpectedToken("&".into(), 1))
+        );
+    }
+
+    #[test]
+    fn parse_compound_assignment() {
+        assert_eq!(
+            parse_script("arr+=(a 'b )'\n  d)"),
+            Ok(vec![simple(&["arr+=(a 'b )'   d)"], 1)])
+        );
+    }
+
+    #[test]
+    fn parse_command_substitution() {
+        assert_eq!(
+            parse_script("echo $(echo \"a)\" (b)) \"$(pwd)\"`ls`; x=(`date` $(id))"),
+            Ok(vec![
+                simple(&["echo", "$(echo \"a)\" (b))", "\"$(pwd)\"`ls`"], 1),
+                simple(&["x=(`date` $(id))"], 1),
+            ])
+        );
+        assert_eq!(parse_script("echo $(pwd"), Err(ParseError::Incomplete));
+    }
+
+    #[test]
+    fn parse_error_lines() {
+        assert_eq!(
+            parse_script("echo a\n\nfor x in a\ndone"),
+            Err(ParseError::UnexpectedToken("done".into(), 4))
+        );
+        assert_eq!(
+            parse_script_at("echo a\n)", 10),
+            Err(ParseError::UnexpectedToken(")".into(), 11))
+        );
+        assert_eq!(
+            parse_script_at("\necho a", 10),
+            Ok(vec![simple(&["echo", "a"], 11)])
+        );
+    }
+
+    #[test]
+    fn parse_incomplete() {
+        assert_eq!(parse_script("greet() {"), Err(ParseError::Incomplete));
+        assert_eq!(parse_script("echo \"abc"), Err(ParseError::Incomplete));
+        assert_eq!(
+            parse_script("echo a )"),
+            Err(ParseError::UnexpectedToken(")".into(), 1))
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let commands = parse_script("f() { echo hi; { pwd; }; }").unwrap_or_default();
+        let Some(Command::FunctionDef(function)) = commands.first() else {
+            panic!("expected a function definition");
+        };
+        assert_eq!(
+            function.to_string(),
+            "f ()\n{\n    echo hi\n    {\n        pwd\n    }\n}"
+        );
+        let reparsed = parse_script(&function.to_string()).unwrap_or_default();
+        let Some(Command::FunctionDef(reparsed)) = reparsed.first() else {
+            panic!("expected a function definition");
+        };
+        assert_eq!(reparsed.to_string(), function.to_string());
+    }
+}
@@ -1,3 +1,7 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::parser::script::{read_command_subst, split_raw_words};
+
 #[must_use]
 #[allow(clippy::missing_panics_doc)]
 pub(crate) fn split_words(input: &str) -> Vec<String> {
@@ -61,3 +65,442 @@ pub(crate) fn split_words(input: &str) -> Vec<String> {
     }
     command_list
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A piece of a single shell word, keeping track of quoting so expansion knows what may be split
+pub(crate) enum WordPart {
+    /// Unquoted literal text
+    Literal(String),
+    /// Literal text that was quoted or escaped
+    Quoted(String),
+    /// A parameter expansion such as `$1`, `${10}`, `$@`, `$HOME` or `${arr[1]}`
+    Param {
+        param: Param,
+        /// Whether the expansion appeared inside double quotes
+        quoted: bool,
+    },
+    /// A `$(command)` or `` `command` `` substitution, replaced by the output of the command
+    CommandSubst { command: String, quoted: bool },
+    /// A `${...}` that does not name a parameter, such as `${x:-default}`, which fails when expanded
+    BadSubstitution(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The parameter referenced by a `$name` or `${...}` expansion
+pub(crate) struct Param {
+    /// Name of the parameter, e.g. `1`, `@` or `HOME`
+    pub(crate) name: String,
+    /// The unexpanded subscript of `${name[index]}`
+    pub(crate) index: Option<String>,
+    /// `${#name}`, expands to the length of the value or the number of elements
+    pub(crate) length: bool,
+    /// `${!name[@]}`, expands to the keys of an array
+    pub(crate) keys: bool,
+}
+
+impl Param {
+    /// Parse the content of `${...}`, returning `None` unless it is a name, digits or a special parameter,
+    /// optionally followed by a subscript
+    fn from_braced(content: &str) -> Option<Self> {
+        let mut param = Param::default();
+        let mut rest = content;
+        if let Some(stripped) = rest.strip_prefix('#').filter(|s| !s.is_empty()) {
+            param.length = true;
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix('!').filter(|s| !s.is_empty()) {
+            param.keys = true;
+            rest = stripped;
+        }
+        let name = match rest.split_once('[') {
+            Some((name, index)) => {
+                let index = index.strip_suffix(']').filter(|index| !index.is_empty())?;
+                param.index = Some(index.to_string());
+                name
+            }
+            None => rest,
+        };
+        let is_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let is_special = param.index.is_none()
+            && (matches!(name, "@" | "*" | "#" | "?" | "$" | "!" | "-")
+                || (!name.is_empty() && name.chars().all(|c| c.is_ascii_digit())));
+        if !is_name && !is_special {
+            return None;
+        }
+        param.name = name.to_string();
+        Some(param)
+    }
+
+    /// Whether this is an `[@]` or `[*]` subscript, expanding to every element of an array
+    pub(crate) fn is_all_elements(&self) -> bool {
+        matches!(self.index.as_deref(), Some("@" | "*"))
+    }
+}
+
+/// Parse a single raw word, as produced by the script parser, into its quoted, unquoted and expanded parts
+#[must_use]
+pub(crate) fn parse_word(raw: &str) -> Vec<WordPart> {
+    let mut parts = vec![];
+    let mut buf = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                push_literal(&mut parts, &mut buf);
+                let quoted: String = chars.by_ref().take_while(|&c| c != '\'').collect();
+                parts.push(WordPart::Quoted(quoted));
+            }
+            '\"' => {
+                push_literal(&mut parts, &mut buf);
+                parse_double_quoted(&mut chars, &mut parts);
+            }
+            '\\' => {
+                push_literal(&mut parts, &mut buf);
+                if let Some(next_char) = chars.next() {
+                    parts.push(WordPart::Quoted(next_char.to_string()));
+                }
+            }
+            '`' => {
+                push_literal(&mut parts, &mut buf);
+                parts.push(parse_backquoted(&mut chars, false));
+            }
+            '$' => match parse_expansion(&mut chars, false) {
+                Some(part) => {
+                    push_literal(&mut parts, &mut buf);
+                    parts.push(part);
+                }
+                None => buf.push(c),
+            },
+            _ => buf.push(c),
+        }
+    }
+    push_literal(&mut parts, &mut buf);
+    parts
+}
+
+fn push_literal(parts: &mut Vec<WordPart>, buf: &mut String) {
+    if !buf.is_empty() {
+        parts.push(WordPart::Literal(std::mem::take(buf)));
+    }
+}
+
+fn parse_double_quoted(chars: &mut Peekable<Chars<'_>>, parts: &mut Vec<WordPart>) {
+    // an empty pair of quotes still produces an (empty) word
+    let mut buf = String::new();
+    let mut has_parts = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\"' => break,
+            '\\' => match chars.next() {
+                Some(escaped @ ('\"' | '\\' | '$' | '`')) => buf.push(escaped),
+                Some(other) => {
+                    buf.push('\\');
+                    buf.push(other);
+                }
+                None => buf.push('\\'),
+            },
+            '`' => {
+                if !buf.is_empty() {
+                    parts.push(WordPart::Quoted(std::mem::take(&mut buf)));
+                }
+                parts.push(parse_backquoted(chars, true));
+                has_parts = true;
+            }
+            '$' => match parse_expansion(chars, true) {
+                Some(part) => {
+                    if !buf.is_empty() {
+                        parts.push(WordPart::Quoted(std::mem::take(&mut buf)));
+                    }
+                    parts.push(part);
+                    has_parts = true;
+                }
+                None => buf.push(c),
+            },
+            _ => buf.push(c),
+        }
+    }
+    if !buf.is_empty() || !has_parts {
+        parts.push(WordPart::Quoted(buf));
+    }
+}
+
+/// Parse the expansion following a `$`, returning `None` (leaving `chars` untouched) when the `$` is literal
+fn parse_expansion(chars: &mut Peekable<Chars<'_>>, quoted: bool) -> Option<WordPart> {
+    if chars.next_if_eq(&'(').is_some() {
+        let mut command = String::new();
+        // the lexer already checked the parentheses are balanced
+        let _ = read_command_subst(chars, &mut command);
+        command.pop();
+        return Some(WordPart::CommandSubst { command, quoted });
+    }
+    if chars.next_if_eq(&'{').is_some() {
+        let content: String = chars.by_ref().take_while(|&c| c != '}').collect();
+        return Some(match Param::from_braced(&content) {
+            Some(param) => WordPart::Param { param, quoted },
+            None => WordPart::BadSubstitution(format!("${{{content}}}")),
+        });
+    }
+    let param = parse_param(chars)?;
+    Some(WordPart::Param { param, quoted })
+}
+
+/// Parse a `` `command` `` substitution after its opening backquote, where backslashes only escape `` \` `` and `$`
+fn parse_backquoted(chars: &mut Peekable<Chars<'_>>, quoted: bool) -> WordPart {
+    let mut command = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => break,
+            '\\' => match chars.next_if(|c| matches!(c, '\\' | '`' | '$')) {
+                Some(escaped) => command.push(escaped),
+                None => command.push(c),
+            },
+            _ => command.push(c),
+        }
+    }
+    WordPart::CommandSubst { command, quoted }
+}
+
+fn parse_param(chars: &mut Peekable<Chars<'_>>) -> Option<Param> {
+    let name = match chars.peek().copied()? {
+        c @ ('0'..='9' | '@' | '*' | '#' | '?' | '$' | '!' | '-') => {
+            chars.next();
+            c.to_string()
+        }
+        c if c.is_ascii_alphabetic() || c == '_' => {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '_') {
+                name.push(c);
+            }
+            name
+        }
+        _ => return None,
+    };
+    Some(Param {
+        name,
+        ..Param::default()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An assignment word such as `name=value`, `name+=value`, `name[index]=value` or `name=(a b c)`
+pub(crate) struct Assignment {
+    pub(crate) name: String,
+    /// The unexpanded subscript of `name[index]=value`
+    pub(crate) index: Option<String>,
+    /// `+=` appends to the current value instead of replacing it
+    pub(crate) append: bool,
+    pub(crate) value: AssignValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The unexpanded value of an assignment
+pub(crate) enum AssignValue {
+    Scalar(String),
+    /// The raw words of a compound `(a b [key]=c)` array value
+    Array(Vec<String>),
+}
+
+/// Parse a raw word as an assignment, returning `None` if the word is not one
+#[must_use]
+pub(crate) fn parse_assignment(raw: &str) -> Option<Assignment> {
+    let name_len = raw.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    let name = &raw[..name_len];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut rest = &raw[name_len..];
+    let index = match rest.strip_prefix('[') {
+        Some(subscript) => {
+            let end = subscript.find(']')?;
+            rest = &subscript[end + 1..];
+            Some(subscript[..end].to_string())
+        }
+        None => None,
+    };
+    let (append, value) = match rest.strip_prefix("+=") {
+        Some(value) => (true, value),
+        None => (false, rest.strip_prefix('=')?),
+    };
+    let value = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(elements) if index.is_none() => AssignValue::Array(split_raw_words(elements).ok()?),
+        _ => AssignValue::Scalar(value.to_string()),
+    };
+    Some(Assignment {
+        name: name.to_string(),
+        index,
+        append,
+        value,
+    })
+}
+
+/// Split a raw `[key]=value` element of a compound array assignment into its key and value
+#[must_use]
+pub(crate) fn split_keyed_element(raw: &str) -> Option<(&str, &str)> {
+    raw.strip_prefix('[')?.split_once("]=")
+}
+
+/// Quote `s` so that it is read back as a single word with the same value, only adding quotes when needed
+#[must_use]
+pub(crate) fn quote(s: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:,+=@%^".contains(c);
+    if !s.is_empty() && s.chars().all(is_safe) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(name: &str, quoted: bool) -> WordPart {
+        WordPart::Param {
+            param: Param {
+                name: name.into(),
+                ..Param::default()
+            },
+            quoted,
+        }
+    }
+
+    #[test]
+    fn parse_word_quoting() {
+        assert_eq!(
+            parse_word("a'$1'\"$1 b\"\\$c"),
+            vec![
+                WordPart::Literal("a".into()),
+                WordPart::Quoted("$1".into()),
+                param("1", true),
+                WordPart::Quoted(" b".into()),
+                WordPart::Quoted("$".into()),
+                WordPart::Literal("c".into()),
+            ]
+        );
+        assert_eq!(parse_word("\"\""), vec![WordPart::Quoted(String::new())]);
+    }
+
+    #[test]
+    fn quote_round_trips() {
+        for s in ["plain", "", "a b", "it's", "$HOME"] {
+            let parts = parse_word(&quote(s));
+            let unquoted: String = parts
+                .iter()
+                .map(|part| match part {
+                    WordPart::Literal(s) | WordPart::Quoted(s) => s.as_str(),
+                    WordPart::Param { .. }
+                    | WordPart::CommandSubst { .. }
+                    | WordPart::BadSubstitution(_) => {
+                        panic!("quoted word should not expand")
+                    }
+                })
+                .collect();
+            assert_eq!(unquoted, s);
+        }
+    }
+
+    #[test]
+    fn parse_word_arrays() {
+        let parts = parse_word("\"${arr[@]}\"${#arr[@]}${!map[@]}");
+        let params: Vec<_> = parts
+            .into_iter()
+            .map(|part| match part {
+                WordPart::Param { param, quoted } => (param, quoted),
+                part => panic!("expected a parameter, got {part:?}"),
+            })
+            .collect();
+        let arr = |length, keys, name: &str| Param {
+            name: name.into(),
+            index: Some("@".into()),
+            length,
+            keys,
+        };
+        assert_eq!(
+            params,
+            vec![
+                (arr(false, false, "arr"), true),
+                (arr(true, false, "arr"), false),
+                (arr(false, true, "map"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_word_bad_substitution() {
+        for raw in [
+            "${x:-def}",
+            "${x-d}",
+            "${}",
+            "${a[]}",
+            "${a[1}",
+            "${1[0]}",
+            "${x y}",
+        ] {
+            assert_eq!(parse_word(raw), vec![WordPart::BadSubstitution(raw.into())]);
+        }
+        assert_eq!(
+            parse_word("\"${x:-a b}\""),
+            vec![WordPart::BadSubstitution("${x:-a b}".into())]
+        );
+        assert_eq!(
+            parse_word("${10}${#}${_x1}"),
+            vec![param("10", false), param("#", false), param("_x1", false)]
+        );
+    }
+
+    #[test]
+    fn parse_word_command_substitution() {
+        assert_eq!(
+            parse_word("a$(echo \"b)\")\"`echo \\$x`\""),
+            vec![
+                WordPart::Literal("a".into()),
+                WordPart::CommandSubst {
+                    command: "echo \"b)\"".into(),
+                    quoted: false
+                },
+                WordPart::CommandSubst {
+                    command: "echo $x".into(),
+                    quoted: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_assignments() {
+        assert_eq!(parse_assignment("echo"), None);
+        assert_eq!(parse_assignment("1x=2"), None);
+        assert_eq!(
+            parse_assignment("arr+=(a 'b c' [5]=d)"),
+            Some(Assignment {
+                name: "arr".into(),
+                index: None,
+                append: true,
+                value: AssignValue::Array(vec!["a".into(), "'b c'".into(), "[5]=d".into()]),
+            })
+        );
+        assert_eq!(
+            parse_assignment("map[$key]=\"v\""),
+            Some(Assignment {
+                name: "map".into(),
+                index: Some("$key".into()),
+                append: false,
+                value: AssignValue::Scalar("\"v\"".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_word_params() {
+        assert_eq!(
+            parse_word("$10${10}$@$HOME_DIR-$"),
+            vec![
+                param("1", false),
+                WordPart::Literal("0".into()),
+                param("10", false),
+                param("@", false),
+                param("HOME_DIR", false),
+                WordPart::Literal("-$".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{parser::parse_assignment, shell::Shell};
+
+impl Shell {
+    /// Expand the aliases in command position of each command of a pipeline, returning the names of the expanded
+    /// aliases and the source the pipeline expands to
+    ///
+    /// When the value of an alias ends in a blank the word following it is checked for an alias as well.
+    /// Aliases currently being expanded are skipped so `alias ls='ls -F'` does not recurse forever
+    pub(crate) fn expand_alias(&self, words: &[String]) -> Option<(Vec<String>, String)> {
+        let mut names = vec![];
+        let commands: Vec<_> = words
+            .split(|word| word == "|")
+            .map(|command| self.expand_command_alias(command, &mut names))
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        Some((names, commands.join(" | ")))
+    }
+
+    /// Expand the alias in command position of a single command of a pipeline, adding the expanded aliases to
+    /// `names`
+    fn expand_command_alias(&self, words: &[String], names: &mut Vec<String>) -> String {
+        // aliases are recognised after any assignments preceding the command
+        let cmd_idx = words
+            .iter()
+            .position(|word| parse_assignment(word).is_none())
+            .unwrap_or(words.len());
+        let expanded = names.len();
+        let is_expandable = |word: &String, names: &[String]| {
+            !names[expanded..].contains(word) && !self.active_aliases.contains(word)
+        };
+
+        let mut source: Vec<String> = words[..cmd_idx].to_vec();
+        let mut rest = &words[cmd_idx..];
+        while let Some(word) = rest.first()
+            && is_expandable(word, names)
+            && let Some(value) = self.aliases.get(word)
+        {
+            names.push(word.clone());
+            source.push(value.clone());
+            rest = &rest[1..];
+            if !value.ends_with([' ', '\t']) {
+                break;
+            }
+        }
+        source.extend(rest.iter().cloned());
+        source.join(" ")
+    }
+}
+
+/// Whether `name` can be used as the name of an alias
+pub(crate) fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(|c: char| {
+            c.is_whitespace() || matches!(c, '/' | '$' | '`' | '=' | '\'' | '"' | '\\')
+        })
+}
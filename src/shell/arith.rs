@@ -0,0 +1,208 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::shell::{error::ShellError, vars::Variables};
+
+// guards against variables referring to each other, e.g. `a=b b=a`
+const MAX_RECURSION_DEPTH: usize = 16;
+
+/// Evaluate an integer arithmetic expression supporting `+ - * / %`, unary signs, parentheses and variable names
+///
+/// # Errors
+/// - `ShellError::Arithmetic` when the expression is malformed or divides by zero
+pub(crate) fn eval_arith(expr: &str, vars: &Variables) -> Result<i64, ShellError> {
+    eval_with_depth(expr, vars, 0)
+}
+
+fn eval_with_depth(expr: &str, vars: &Variables, depth: usize) -> Result<i64, ShellError> {
+    let mut arith = Arith {
+        expr,
+        chars: expr.chars().peekable(),
+        vars,
+        depth,
+    };
+    if expr.trim().is_empty() {
+        return Ok(0);
+    }
+    let value = arith.parse_sum()?;
+    arith.skip_whitespace();
+    match arith.chars.peek().copied() {
+        None => Ok(value),
+        Some(c) => Err(arith.error(&format!(
+            "syntax error: invalid arithmetic operator (error token is \"{c}\")"
+        ))),
+    }
+}
+
+struct Arith<'a> {
+    expr: &'a str,
+    chars: Peekable<Chars<'a>>,
+    vars: &'a Variables,
+    depth: usize,
+}
+
+impl Arith<'_> {
+    fn error(&self, msg: &str) -> ShellError {
+        ShellError::Arithmetic(self.expr.to_string(), msg.to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn next_operator(&mut self, operators: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.next_if(|c| operators.contains(c))
+    }
+
+    fn parse_sum(&mut self) -> Result<i64, ShellError> {
+        let mut value = self.parse_product()?;
+        while let Some(op) = self.next_operator(&['+', '-']) {
+            let rhs = self.parse_product()?;
+            value = if op == '+' {
+                value.wrapping_add(rhs)
+            } else {
+                value.wrapping_sub(rhs)
+            };
+        }
+        Ok(value)
+    }
+
+    fn parse_product(&mut self) -> Result<i64, ShellError> {
+        let mut value = self.parse_unary()?;
+        while let Some(op) = self.next_operator(&['*', '/', '%']) {
+            let rhs = self.parse_unary()?;
+            value = match op {
+                '*' => value.wrapping_mul(rhs),
+                _ if rhs == 0 => return Err(self.error("division by 0")),
+                '/' => value.wrapping_div(rhs),
+                _ => value.wrapping_rem(rhs),
+            };
+        }
+        Ok(value)
+    }
+
+    fn parse_unary(&mut self) -> Result<i64, ShellError> {
+        match self.next_operator(&['+', '-']) {
+            Some('-') => Ok(self.parse_unary()?.wrapping_neg()),
+            Some(_) => self.parse_unary(),
+            None => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<i64, ShellError> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('(') => {
+                self.chars.next();
+                let value = self.parse_sum()?;
+                if self.next_operator(&[')']).is_none() {
+                    return Err(self.error("missing `)'"));
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(c) = self.chars.next_if(char::is_ascii_alphanumeric) {
+                    digits.push(c);
+                }
+                digits.parse().map_err(|_| {
+                    self.error(&format!(
+                        "value too great for base (error token is \"{digits}\")"
+                    ))
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    name.push(c);
+                }
+                if self.depth >= MAX_RECURSION_DEPTH {
+                    return Err(self.error("expression recursion level exceeded"));
+                }
+                // the value of a variable is itself evaluated as an expression, dynamic variables included
+                let value = match self.vars.dynamic.value(&name) {
+                    Some(value) => value,
+                    None => self.vars.value(&name).unwrap_or_default().to_string(),
+                };
+                eval_with_depth(&value, self.vars, self.depth + 1)
+            }
+            _ => Err(self.error("syntax error: operand expected")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(assignments: &[(&str, &str)]) -> Variables {
+        let mut vars = Variables::default();
+        for (name, value) in assignments {
+            assert!(vars.set(name, value).is_ok());
+        }
+        vars
+    }
+
+    #[test]
+    fn eval_precedence() {
+        let vars = vars(&[]);
+        assert_eq!(eval_arith("1 + 2 * 3", &vars).ok(), Some(7));
+        assert_eq!(eval_arith("(1 + 2) * 3", &vars).ok(), Some(9));
+        assert_eq!(eval_arith("10 - 4 - 3", &vars).ok(), Some(3));
+        assert_eq!(eval_arith("7 / 2 * 2 + 7 % 2", &vars).ok(), Some(7));
+        assert_eq!(eval_arith("-2 * -(3 + +1)", &vars).ok(), Some(8));
+        assert_eq!(eval_arith("  ", &vars).ok(), Some(0));
+    }
+
+    #[test]
+    fn eval_variables() {
+        let vars = vars(&[("x", "4"), ("expr", "x * 2"), ("loop", "loop")]);
+        assert_eq!(eval_arith("x + 1", &vars).ok(), Some(5));
+        // values are expressions themselves, unset and empty variables are 0
+        assert_eq!(eval_arith("expr + unset", &vars).ok(), Some(8));
+        assert!(matches!(
+            eval_arith("loop", &vars),
+            Err(ShellError::Arithmetic(_, msg)) if msg == "expression recursion level exceeded"
+        ));
+    }
+
+    #[test]
+    fn eval_dynamic_variables() {
+        let mut vars = vars(&[]);
+        vars.dynamic.lineno = 7;
+        vars.dynamic.set_seconds(30);
+        assert_eq!(eval_arith("LINENO + 1", &vars).ok(), Some(8));
+        assert_eq!(eval_arith("SECONDS", &vars).ok(), Some(30));
+        assert!(eval_arith("RANDOM", &vars).is_ok_and(|n| (0..32768).contains(&n)));
+    }
+
+    #[test]
+    fn eval_errors() {
+        let vars = vars(&[("zero", "0")]);
+        for expr in ["1 / 0", "5 % zero"] {
+            assert!(matches!(
+                eval_arith(expr, &vars),
+                Err(ShellError::Arithmetic(_, msg)) if msg == "division by 0"
+            ));
+        }
+        assert!(matches!(
+            eval_arith("(1 + 2", &vars),
+            Err(ShellError::Arithmetic(_, msg)) if msg == "missing `)'"
+        ));
+        assert!(matches!(
+            eval_arith("1 +", &vars),
+            Err(ShellError::Arithmetic(_, msg)) if msg == "syntax error: operand expected"
+        ));
+        assert!(matches!(
+            eval_arith("2 ^ 3", &vars),
+            Err(ShellError::Arithmetic(_, msg)) if msg.contains("error token is \"^\"")
+        ));
+        assert!(matches!(
+            eval_arith("08x", &vars),
+            Err(ShellError::Arithmetic(_, msg)) if msg.contains("value too great for base")
+        ));
+    }
+}
@@ -1,11 +1,9 @@
-use std::{iter::Peekable, process::Child};
-
-use rustyline::history::FileHistory;
+use std::{iter::Peekable, os::fd::OwnedFd};
 
 use crate::{
     commands::{Builtin, invoke_builtin},
     parser::{Token, split_words},
-    shell::{error::ShellError, pipeline, redirect},
+    shell::{Shell, error::ShellError, pipeline, redirect},
 };
 
 pub(crate) fn handle_builtin<'a, I>(
@@ -13,33 +11,42 @@ pub(crate) fn handle_builtin<'a, I>(
     args: &[String],
     token_iter: &mut Peekable<I>,
     prev_command_output: Option<String>,
-    _prev_command: Option<&mut Child>,
-    history: &mut FileHistory,
+    prev_stdout: Option<OwnedFd>,
+    shell: &mut Shell,
 ) -> Result<(), ShellError>
 where
     I: Iterator<Item = &'a Token>,
 {
+    // a stage of a pipeline runs in a subshell in bash, so `exit` or `return` there only ends that stage
+    let piped = prev_command_output.is_some()
+        || prev_stdout.is_some()
+        || matches!(token_iter.peek(), Some(Token::Pipe));
+    // builtins do not read stdin, closing the previous stage's output lets it finish instead of filling the pipe
+    drop(prev_stdout);
+
     let mut all_args = vec![];
     for s in args {
         all_args.push(s.clone());
     }
 
-    if let Some(out) = prev_command_output {
+    if let Some(out) = prev_command_output
+        && builtin.takes_piped_words()
+    {
         let extra_args = split_words(&out);
 
         all_args.extend(extra_args);
     }
 
-    // if let Some(child) = prev_command
-    // && let Some(stdout) = child.stdout.as_mut()
-    // {
-    //     // builtins do not read stdin
-    // }
-
-    let Some(builtin_out) = invoke_builtin(builtin, &all_args, history)? else {
-        // early return for cd
-        return Ok(());
-    };
+    // builtins that change control flow or fail report their own status, `exit` and `return` default to `$?`
+    if !matches!(builtin, Builtin::Exit | Builtin::Return) {
+        shell.last_status = 0;
+    }
+    let flow = shell.flow;
+    // builtins without output, like `cd`, still pass an empty output on to a redirection or the next stage
+    let builtin_out = invoke_builtin(builtin, &all_args, shell)?.unwrap_or_default();
+    if piped {
+        shell.flow = flow;
+    }
 
     match token_iter.next() {
         None => print!("{builtin_out}"),
@@ -47,7 +54,7 @@ where
             redirect::redirect_builtin_output(redirect_symb, &builtin_out, token_iter)?;
         }
         Some(Token::Pipe) => {
-            pipeline::run_pipeline_builtin(builtin_out, token_iter, history)?;
+            pipeline::run_pipeline_builtin(builtin_out, token_iter, shell)?;
         }
         Some(_t) => unreachable!(),
     }
@@ -0,0 +1,52 @@
+use std::{
+    io::{self, Write},
+    os::fd::{AsFd, OwnedFd},
+};
+
+use nix::unistd::{dup2_stderr, dup2_stdout};
+
+use crate::shell::error::ShellError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Standard stream of the shell that a guard or a redirection replaces
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Guard that points the shell's own stdout or stderr at another file descriptor,
+/// restoring the original one when dropped.
+/// Functions run inside the shell process, so this is how their output is redirected
+pub(crate) struct StdioGuard {
+    stream: Stream,
+    saved: OwnedFd,
+}
+
+impl StdioGuard {
+    pub(crate) fn redirect(stream: Stream, fd: impl AsFd) -> Result<Self, ShellError> {
+        io::stdout()
+            .flush()
+            .map_err(ShellError::FailedStdoutFlush)?;
+        let saved = match stream {
+            Stream::Stdout => io::stdout().as_fd().try_clone_to_owned(),
+            Stream::Stderr => io::stderr().as_fd().try_clone_to_owned(),
+        }
+        .map_err(ShellError::StdioRedirectFailure)?;
+        match stream {
+            Stream::Stdout => dup2_stdout(fd),
+            Stream::Stderr => dup2_stderr(fd),
+        }
+        .map_err(|e| ShellError::StdioRedirectFailure(e.into()))?;
+        Ok(Self { stream, saved })
+    }
+}
+
+impl Drop for StdioGuard {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        let _ = match self.stream {
+            Stream::Stdout => dup2_stdout(&self.saved),
+            Stream::Stderr => dup2_stderr(&self.saved),
+        };
+    }
+}
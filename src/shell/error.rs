@@ -31,12 +31,42 @@ pub enum ShellError {
     WriteFileFailure(String, PathBuf, #[source] io::Error),
     #[error("Child stdin was not piped before command {0:?}")]
     ChildStdinNotPiped(Box<Command>),
-    #[error("Failed to take stdout of previous command for piping into next command")]
-    FailedToTakeStdout,
     #[error("Attempted to redirect into {0:?}")]
     NoFileForRedirection(Option<Token>),
     #[error("Failed to create dirs required for {0} due to {1}")]
     CouldNotCreateParentDir(PathBuf, #[source] io::Error),
     #[error("Failed to open file {0} due to {1}")]
     FailedToOpenFile(PathBuf, #[source] io::Error),
+    #[error("{0}")]
+    ParseError(#[from] crate::parser::error::ParseError),
+    #[error("Failed to run a subshell due to {0}")]
+    SubshellFailure(#[source] io::Error),
+    #[error("Failed to redirect standard streams due to {0}")]
+    StdioRedirectFailure(#[source] io::Error),
+    #[error("{0}: readonly variable")]
+    ReadonlyVariable(String),
+    #[error("unset: {0}: cannot unset: readonly variable")]
+    CannotUnsetReadonly(String),
+    #[error("{0}: `{1}': not a valid identifier")]
+    InvalidIdentifier(&'static str, String),
+    #[error("{0}: {1}")]
+    Arithmetic(String, String),
+    #[error("{0}: bad array subscript")]
+    BadArraySubscript(String),
+    #[error("{0}: must use subscript when assigning associative array")]
+    AssocSubscriptRequired(String),
+    #[error("{0}: cannot convert {1}")]
+    ArrayConversion(String, &'static str),
+    #[error("{0}: unbound variable")]
+    UnboundVariable(String),
+    #[error("{0}: bad substitution")]
+    BadSubstitution(String),
+    #[error("{0}: {1}")]
+    ReadScript(String, #[source] io::Error),
+    #[error("{file}: line {line}: {source}")]
+    Syntax {
+        file: String,
+        line: usize,
+        source: crate::parser::error::ParseError,
+    },
 }
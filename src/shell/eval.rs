@@ -0,0 +1,279 @@
+use std::rc::Rc;
+
+use nix::sys::signal::Signal;
+
+use crate::{
+    parser::{
+        AssignValue, Assignment, Command, Function, Token, parse_assignment, parse_script, quote,
+        tokenize_input,
+    },
+    shell::{
+        Pipeline, Shell, ShellOption, Trap,
+        error::ShellError,
+        handle_command,
+        signals::{self, InterruptGuard},
+        vars::AssignedValue,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Pending change of control flow, set by `return` and `exit` and honoured by every command list being executed
+pub(crate) enum Flow {
+    /// Leave the innermost function call
+    Return,
+    /// Leave the shell
+    Exit,
+}
+
+impl Shell {
+    /// Execute a list of commands, stopping early when `return` or `exit` is requested
+    /// errors of a single command are printed to stderr and do not stop the remaining commands
+    pub(crate) fn execute(&mut self, commands: &[Command]) {
+        // `set -n` only checks the syntax of scripts, an interactive shell could never turn it off again
+        // nothing runs once `exit` was requested, e.g. from a startup file
+        if (self.option(ShellOption::Noexec) && !self.interactive) || self.flow == Some(Flow::Exit)
+        {
+            return;
+        }
+        for command in commands {
+            if signals::interrupted() {
+                // Ctrl-C abandons the rest of the command line, and ends a shell that is not interactive
+                self.last_status = 130;
+                if !self.interactive {
+                    self.flow = Some(Flow::Exit);
+                }
+                break;
+            }
+            if let Err(e) = self.execute_command(command) {
+                eprintln!("{}{e}", self.error_prefix());
+                self.last_status = 1;
+                // like bash, a script stops at the first expansion of an unset parameter under `set -u`
+                if matches!(e, ShellError::UnboundVariable(_)) && !self.interactive {
+                    self.flow = Some(Flow::Exit);
+                }
+            }
+            self.run_pending_traps();
+            if self.flow.is_none()
+                && self.last_status != 0
+                && matches!(command, Command::Simple { .. })
+            {
+                self.run_command_trap(Trap::Err);
+                if self.option(ShellOption::Errexit) {
+                    self.flow = Some(Flow::Exit);
+                }
+            }
+            if self.flow.is_some() {
+                break;
+            }
+        }
+    }
+
+    fn execute_command(&mut self, command: &Command) -> Result<(), ShellError> {
+        match command {
+            Command::Simple { words, line } => {
+                self.vars.dynamic.lineno = *line;
+                self.run_command_trap(Trap::Debug);
+                match self.expand_alias(words) {
+                    Some((names, source)) => self.run_alias(names, &source),
+                    None => self.run_simple(words),
+                }
+            }
+            Command::Group(commands) => {
+                self.execute(commands);
+                Ok(())
+            }
+            Command::FunctionDef(function) => {
+                self.functions
+                    .insert(function.name.clone(), Rc::clone(function));
+                self.last_status = 0;
+                Ok(())
+            }
+            Command::For { var, words, body } => self.run_for(var, words.as_deref(), body),
+            Command::Time { posix, command } => {
+                self.run_timed(*posix, command.as_deref());
+                Ok(())
+            }
+            Command::Background(command) => {
+                self.run_background(command);
+                Ok(())
+            }
+        }
+    }
+
+    fn run_alias(&mut self, names: Vec<String>, source: &str) -> Result<(), ShellError> {
+        let commands = parse_script(source)?;
+        let active = self.active_aliases.len();
+        self.active_aliases.extend(names);
+        self.execute(&commands);
+        self.active_aliases.truncate(active);
+        Ok(())
+    }
+
+    fn run_simple(&mut self, words: &[String]) -> Result<(), ShellError> {
+        let assignments: Vec<_> = words
+            .iter()
+            .map_while(|word| parse_assignment(word))
+            .collect();
+        self.substitution_status = None;
+        // the command words are expanded before the assignments preceding them take effect
+        let fields = self.expand_words(&words[assignments.len()..])?;
+        if self.option(ShellOption::Xtrace) && !fields.is_empty() {
+            self.trace(&fields);
+        }
+        if fields.is_empty() {
+            // without a command the assignments set shell variables, `$?` still expanding to the previous status
+            for assignment in &assignments {
+                self.assign(assignment)?;
+            }
+            self.last_status = self.substitution_status.unwrap_or(0);
+            return Ok(());
+        }
+        // a stopped job is listed the way its command was written
+        let command = words.join(" ");
+        if assignments.is_empty() {
+            return self.run_fields(fields, command);
+        }
+
+        // assignments before a command only apply to that command, and are exported to it
+        self.vars.push_scope();
+        let outer = self.temporary_scope.replace(self.vars.depth());
+        let res = self
+            .assign_temporary(&assignments)
+            .and_then(|()| self.run_fields(fields, command));
+        self.end_temporary_assignments();
+        self.temporary_scope = outer;
+        res
+    }
+
+    /// Drop the assignments preceding a command, which a pipeline does as soon as its first command started so
+    /// they do not apply to the rest of it
+    pub(crate) fn end_temporary_assignments(&mut self) {
+        if self
+            .temporary_scope
+            .take()
+            .is_some_and(|depth| depth == self.vars.depth())
+        {
+            self.vars.pop_scope();
+        }
+    }
+
+    /// Print a command about to be run to stderr for `set -x`, prefixed by `PS4`
+    fn trace(&self, fields: &[String]) {
+        let prefix = self.vars.value("PS4").unwrap_or("+ ");
+        let command: Vec<_> = fields.iter().map(|field| quote(field)).collect();
+        eprintln!("{prefix}{}", command.join(" "));
+    }
+
+    fn assign_temporary(&mut self, assignments: &[Assignment]) -> Result<(), ShellError> {
+        for assignment in assignments {
+            let name = &assignment.name;
+            let var = self.vars.get(name).cloned().unwrap_or_default();
+            if var.attrs.readonly {
+                return Err(ShellError::ReadonlyVariable(name.clone()));
+            }
+            let temporary = self.vars.declare(name, true);
+            *temporary = var;
+            temporary.attrs.exported = true;
+            self.assign(assignment)?;
+        }
+        Ok(())
+    }
+
+    /// Run the expanded `fields` of the simple command `command`, which is how the command appears as a job
+    fn run_fields(&mut self, fields: Vec<String>, command: String) -> Result<(), ShellError> {
+        let Some(tokens) = tokenize_input(fields) else {
+            return Ok(());
+        };
+        let mut token_iter = tokens.iter().peekable();
+
+        let Some(Token::Command(cmd_str)) = token_iter.next() else {
+            // this path should be unreachable as the first token is always a command token.
+            // if the input was empty then `tokenize_input` would have returned None already
+            unreachable!();
+        };
+
+        let mut args = vec![];
+        while let Some(Token::Arg(s)) = token_iter.peek() {
+            args.push(s.clone());
+            token_iter.next();
+        }
+
+        // the commands of a function in a pipeline join the pipeline's process group instead of starting their own
+        let starts_pipeline = self.pipeline.is_none();
+        if starts_pipeline {
+            self.pipeline = Some(Pipeline::new(command));
+        }
+        // a shell that is not interactive outlives Ctrl-C while its command runs, the command's death decides
+        // unless a trap on `SIGINT` handles it
+        let _guard = (starts_pipeline
+            && self.interrupt_guard.is_none()
+            && !self.traps.contains_key(&Trap::Signal(Signal::SIGINT)))
+        .then(InterruptGuard::wait);
+        let res = handle_command(cmd_str, &args, &mut token_iter, self);
+        if starts_pipeline {
+            self.finish_pipeline();
+        }
+        res
+    }
+
+    /// Expand and perform an assignment statement such as `name=value`, `arr[i]+=value` or `arr=(a b c)`
+    fn assign(&mut self, assignment: &Assignment) -> Result<(), ShellError> {
+        let index = match &assignment.index {
+            Some(index) => Some(self.expand_to_string(index)?),
+            None => None,
+        };
+        let value = match &assignment.value {
+            AssignValue::Scalar(value) => {
+                let value = self.expand_to_string(value)?;
+                if index.is_none() && self.assign_dynamic(&assignment.name, &value)? {
+                    return Ok(());
+                }
+                AssignedValue::Scalar(value)
+            }
+            AssignValue::Array(elements) => {
+                AssignedValue::Array(self.expand_array_elements(elements)?)
+            }
+        };
+        self.vars
+            .assign(&assignment.name, index.as_deref(), value, assignment.append)
+    }
+
+    fn run_for(
+        &mut self,
+        var: &str,
+        words: Option<&[String]>,
+        body: &[Command],
+    ) -> Result<(), ShellError> {
+        let items = match words {
+            Some(words) => self.expand_words(words)?,
+            None => self.positional.clone(),
+        };
+        self.last_status = 0;
+        for item in items {
+            self.vars.set(var, &item)?;
+            self.execute(body);
+            if self.flow.is_some() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Call `function` with `args` as its positional parameters, restoring the caller's parameters afterwards
+    pub(crate) fn call_function(&mut self, function: &Function, args: &[String]) {
+        let saved_positional = std::mem::replace(&mut self.positional, args.to_vec());
+        self.call_depth += 1;
+        self.vars.push_scope();
+        self.last_status = 0;
+
+        self.execute(&function.body);
+        self.run_trap(Trap::Return);
+
+        self.vars.pop_scope();
+        self.call_depth -= 1;
+        self.positional = saved_positional;
+        if self.flow == Some(Flow::Return) {
+            self.flow = None;
+        }
+    }
+}
@@ -1,14 +1,13 @@
 use std::{
     io::Write,
     iter::Peekable,
-    process::{Child, Command, Stdio},
+    os::{fd::OwnedFd, unix::process::ExitStatusExt},
+    process::{Command, ExitStatus, Stdio},
 };
 
-use rustyline::history::FileHistory;
-
 use crate::{
     parser::Token,
-    shell::{error::ShellError, pipeline, redirect},
+    shell::{Shell, error::ShellError, pipeline, redirect, signals},
 };
 
 pub(crate) fn handle_external_exec<'a, I>(
@@ -16,8 +15,8 @@ pub(crate) fn handle_external_exec<'a, I>(
     args: &[String],
     token_iter: &mut Peekable<I>,
     prev_command_output: Option<String>,
-    prev_command: Option<&mut Child>,
-    history: &mut FileHistory,
+    prev_stdout: Option<OwnedFd>,
+    shell: &mut Shell,
 ) -> Result<(), ShellError>
 where
     I: Iterator<Item = &'a Token>,
@@ -25,27 +24,26 @@ where
     let mut command = Command::new(cmd_str);
 
     command.args(args);
+    // children only see the exported shell variables, not the environment the shell was started with
+    command.env_clear().envs(shell.vars.exported());
+    if let Some(stdout) = prev_stdout {
+        command.stdin(stdout);
+    }
 
     match token_iter.next() {
         // no more tokens
         None => {
             if prev_command_output.is_some() {
                 command.stdin(Stdio::piped());
-            } else if prev_command.is_some() {
-                match prev_command.and_then(|p| p.stdout.take()) {
-                    Some(stdout) => {
-                        command.stdin(stdout);
-                    }
-                    None => return Err(ShellError::FailedToTakeStdout)?,
-                }
             }
 
-            let mut child = command
-                .spawn()
-                .map_err(|e| ShellError::CommandSpawnFailure {
-                    name: command.get_program().to_os_string(),
-                    source: e,
-                })?;
+            let mut child =
+                shell
+                    .spawn(&mut command)
+                    .map_err(|e| ShellError::CommandSpawnFailure {
+                        name: command.get_program().to_os_string(),
+                        source: e,
+                    })?;
 
             #[allow(clippy::expect_used)]
             if let Some(prev) = prev_command_output {
@@ -58,23 +56,32 @@ where
                     .map_err(|e| ShellError::WriteStdinFailure(prev, stdin, e))?;
             }
 
-            child
-                .wait()
-                .map_err(|e| ShellError::CommandWaitFailure(child, e))?;
+            shell.last_status = match shell.wait_child(&mut child) {
+                Ok(status) => status,
+                Err(e) => return Err(ShellError::CommandWaitFailure(child, e)),
+            };
         }
         Some(Token::Redirect(redirect_symb)) => {
-            redirect::redirect_external(&mut command, redirect_symb, token_iter)?;
+            shell.last_status =
+                redirect::redirect_external(&mut command, redirect_symb, token_iter, shell)?;
         }
         Some(Token::Pipe) => {
-            pipeline::run_pipeline_external(
-                command,
-                prev_command,
-                prev_command_output,
-                token_iter,
-                history,
-            )?;
+            pipeline::run_pipeline_external(command, prev_command_output, token_iter, shell)?;
         }
         Some(t) => unreachable!("found unhandled token: {:?}", t),
     }
     Ok(())
 }
+
+/// Convert the exit status of a child into the numeric status reported by the shell,
+/// children killed by a signal report 128 + the signal number like in bash
+/// a child killed by Ctrl-C interrupts the shell's command line as if the shell had received it
+pub(crate) fn status_code(status: ExitStatus) -> i32 {
+    if status.signal() == Some(libc::SIGINT) {
+        signals::set_interrupted();
+    }
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
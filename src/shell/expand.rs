@@ -0,0 +1,387 @@
+use std::io::{self, Read, Write};
+
+use nix::unistd::{ForkResult, dup2_stdout, fork};
+
+use crate::{
+    parser::{
+        AssignValue, Assignment, Param, WordPart, parse_assignment, parse_script, parse_word,
+        quote, split_keyed_element,
+    },
+    shell::{Shell, ShellError, ShellOption, jobs::wait_subshell, vars::Value},
+};
+
+// used when `IFS` is unset
+const DEFAULT_IFS: &str = " \t\n";
+
+/// Fields produced while expanding a single word
+struct Fields {
+    done: Vec<String>,
+    current: String,
+    // set once the current field is known to exist, even if it ends up empty (e.g. `""`)
+    started: bool,
+    /// characters the results of unquoted expansions are split on, `None` when the word is not split at all
+    ifs: Option<String>,
+    // set after IFS whitespace ended a field, so a following non-whitespace IFS character belongs to the same separator
+    after_ifs_whitespace: bool,
+}
+
+impl Fields {
+    fn new(ifs: Option<String>) -> Self {
+        Self {
+            done: vec![],
+            current: String::new(),
+            started: false,
+            ifs,
+            after_ifs_whitespace: false,
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.current.push_str(s);
+        self.started = true;
+        self.after_ifs_whitespace = false;
+    }
+
+    /// Push the result of an unquoted expansion, splitting it into fields on the characters of IFS:
+    /// runs of IFS whitespace separate fields and are trimmed at the start and end,
+    /// while every other IFS character ends a field, even an empty one
+    fn push_split(&mut self, s: &str) {
+        let Some(ifs) = self.ifs.take() else {
+            if !s.is_empty() {
+                self.push_str(s);
+            }
+            return;
+        };
+        for c in s.chars() {
+            if !ifs.contains(c) {
+                self.current.push(c);
+                self.started = true;
+                self.after_ifs_whitespace = false;
+            } else if matches!(c, ' ' | '\t' | '\n') {
+                if self.started {
+                    self.finish_field();
+                    self.after_ifs_whitespace = true;
+                }
+            } else {
+                if self.started || !self.after_ifs_whitespace {
+                    self.started = true;
+                    self.finish_field();
+                }
+                self.after_ifs_whitespace = false;
+            }
+        }
+        self.ifs = Some(ifs);
+    }
+
+    fn finish_field(&mut self) {
+        if self.started {
+            self.done.push(std::mem::take(&mut self.current));
+        }
+        self.started = false;
+    }
+}
+
+/// The result of expanding a single parameter
+enum Expansion {
+    Single(String),
+    /// `$@` and `${arr[@]}` expand to a separate field per element
+    Fields(Vec<String>),
+}
+
+impl Shell {
+    /// Expand the raw words of a simple command into the fields passed to the command
+    ///
+    /// # Errors
+    /// - `ShellError::Arithmetic` or `ShellError::BadArraySubscript` if an array subscript is invalid
+    /// - `ShellError::ParseError` if a command substitution cannot be parsed
+    pub(crate) fn expand_words(&mut self, words: &[String]) -> Result<Vec<String>, ShellError> {
+        // assignments passed to declaration builtins are not split, like the value of an assignment statement
+        let is_declaration = words.first().is_some_and(|word| {
+            matches!(
+                word.as_str(),
+                "declare" | "typeset" | "local" | "readonly" | "export"
+            )
+        });
+        let mut fields = vec![];
+        for word in words {
+            match parse_assignment(word) {
+                // compound assignments stay a single word so `declare arr=(a b)` sees all elements
+                Some(Assignment {
+                    name,
+                    append,
+                    value: AssignValue::Array(elements),
+                    ..
+                }) => {
+                    let elements: Vec<_> = self
+                        .expand_array_elements(&elements)?
+                        .into_iter()
+                        .map(|(key, value)| match key {
+                            Some(key) => format!("[{}]={}", quote(&key), quote(&value)),
+                            None => quote(&value),
+                        })
+                        .collect();
+                    let op = if append { "+=" } else { "=" };
+                    fields.push(format!("{name}{op}({})", elements.join(" ")));
+                }
+                Some(_) if is_declaration => fields.push(self.expand_to_string(word)?),
+                _ => fields.extend(self.expand_word(&parse_word(word), true)?),
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Expand a raw word without splitting it into fields, as done for the value of an assignment
+    ///
+    /// # Errors
+    /// - `ShellError::Arithmetic` or `ShellError::BadArraySubscript` if an array subscript is invalid
+    /// - `ShellError::ParseError` if a command substitution cannot be parsed
+    pub(crate) fn expand_to_string(&mut self, word: &str) -> Result<String, ShellError> {
+        Ok(self.expand_word(&parse_word(word), false)?.join(" "))
+    }
+
+    /// Expand the raw elements of a compound array assignment into their optional subscripts and values
+    ///
+    /// # Errors
+    /// - `ShellError::Arithmetic` or `ShellError::BadArraySubscript` if an array subscript is invalid
+    /// - `ShellError::ParseError` if a command substitution cannot be parsed
+    pub(crate) fn expand_array_elements(
+        &mut self,
+        elements: &[String],
+    ) -> Result<Vec<(Option<String>, String)>, ShellError> {
+        let mut expanded = vec![];
+        for element in elements {
+            match split_keyed_element(element) {
+                Some((key, value)) => expanded.push((
+                    Some(self.expand_to_string(key)?),
+                    self.expand_to_string(value)?,
+                )),
+                None => expanded.extend(
+                    self.expand_word(&parse_word(element), true)?
+                        .into_iter()
+                        .map(|value| (None, value)),
+                ),
+            }
+        }
+        Ok(expanded)
+    }
+
+    fn expand_word(&mut self, parts: &[WordPart], split: bool) -> Result<Vec<String>, ShellError> {
+        let ifs = split.then(|| self.param("IFS").unwrap_or_else(|| DEFAULT_IFS.into()));
+        let mut fields = Fields::new(ifs);
+        for part in parts {
+            match part {
+                WordPart::Literal(s) | WordPart::Quoted(s) => fields.push_str(s),
+                WordPart::Param { param, quoted } => match self.expand_param(param, *quoted)? {
+                    Expansion::Single(value) if *quoted => fields.push_str(&value),
+                    Expansion::Single(value) => fields.push_split(&value),
+                    Expansion::Fields(values) => {
+                        for (i, value) in values.iter().enumerate() {
+                            if i > 0 {
+                                fields.finish_field();
+                            }
+                            if *quoted {
+                                fields.push_str(value);
+                            } else {
+                                fields.push_split(value);
+                            }
+                        }
+                    }
+                },
+                WordPart::BadSubstitution(raw) => {
+                    return Err(ShellError::BadSubstitution(raw.clone()));
+                }
+                WordPart::CommandSubst { command, quoted } => {
+                    let output = self.command_substitution(command)?;
+                    if *quoted {
+                        fields.push_str(&output);
+                    } else {
+                        fields.push_split(&output);
+                    }
+                }
+            }
+        }
+        fields.finish_field();
+        Ok(fields.done)
+    }
+
+    /// Run `command` in a subshell, a forked copy of the shell, and return its output without trailing newlines
+    /// `$?` is set to the status of the subshell
+    fn command_substitution(&mut self, command: &str) -> Result<String, ShellError> {
+        let commands = parse_script(command)?;
+        let (mut reader, writer) = io::pipe().map_err(ShellError::SubshellFailure)?;
+        // anything still buffered would otherwise be written by both processes
+        let _ = io::stdout().flush();
+        // SAFETY: a substitution is expanded on the shell's only thread, pipeline stages and redirections fork
+        // or spawn processes instead of threads, so no lock can be held in the child while it runs the commands
+        #[allow(unsafe_code)]
+        match unsafe { fork() } {
+            Ok(ForkResult::Child) => {
+                drop(reader);
+                let _ = dup2_stdout(&writer);
+                drop(writer);
+                self.enter_subshell();
+                self.execute(&commands);
+                self.run_exit_trap();
+                let _ = io::stdout().flush();
+                std::process::exit(self.last_status);
+            }
+            Ok(ForkResult::Parent { child }) => {
+                // the pipe is only at its end once the child's copy of the write end is closed as well
+                drop(writer);
+                let mut output = vec![];
+                let read = reader.read_to_end(&mut output);
+                self.last_status = wait_subshell(child).map_err(ShellError::SubshellFailure)?;
+                self.substitution_status = Some(self.last_status);
+                read.map_err(ShellError::SubshellFailure)?;
+                let output = String::from_utf8_lossy(&output);
+                Ok(output.trim_end_matches('\n').to_string())
+            }
+            Err(e) => Err(ShellError::SubshellFailure(e.into())),
+        }
+    }
+
+    fn expand_param(&mut self, param: &Param, quoted: bool) -> Result<Expansion, ShellError> {
+        let name = param.name.as_str();
+        let value = self.vars.get(name).and_then(|var| var.value.as_ref());
+        let all_elements = param.is_all_elements();
+        // `"${arr[*]}"` and `"$*"` join the elements with the first character of IFS
+        let joined =
+            quoted && (param.index.as_deref() == Some("*") || (name == "*" && !all_elements));
+        let elements: Option<Vec<String>> = if param.keys && all_elements {
+            Some(value.map(Value::keys).unwrap_or_default())
+        } else if all_elements {
+            Some(
+                value
+                    .map(Value::values)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            )
+        } else if name == "@" || name == "*" {
+            Some(self.positional.clone())
+        } else {
+            None
+        };
+
+        let expansion = match elements {
+            _ if param.length && (all_elements || name == "@" || name == "*") => {
+                let count = elements.map_or(0, |elements| elements.len());
+                Expansion::Single(count.to_string())
+            }
+            Some(elements) if joined => Expansion::Single(elements.join(&self.ifs_separator())),
+            Some(elements) => Expansion::Fields(elements),
+            None => {
+                let value = match &param.index {
+                    Some(index) => {
+                        let key = self.expand_to_string(index)?;
+                        self.vars.element(name, &key)?.map(String::from)
+                    }
+                    // `${!name}` expands the parameter named by the value of `name`
+                    None if param.keys => self.param(name).and_then(|name| self.param(&name)),
+                    None => self.param(name),
+                };
+                let value = match value {
+                    Some(value) => value,
+                    None if self.option(ShellOption::Nounset) => {
+                        let name = match &param.index {
+                            Some(index) => format!("{name}[{index}]"),
+                            None => name.to_string(),
+                        };
+                        return Err(ShellError::UnboundVariable(name));
+                    }
+                    None => String::new(),
+                };
+                if param.length {
+                    Expansion::Single(value.chars().count().to_string())
+                } else {
+                    Expansion::Single(value)
+                }
+            }
+        };
+        Ok(expansion)
+    }
+
+    /// Look up the value of a parameter by name
+    pub(crate) fn param(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.special_param(name) {
+            return Some(value);
+        }
+        match name {
+            "#" => Some(self.positional.len().to_string()),
+            "?" => Some(self.last_status.to_string()),
+            "*" => Some(self.positional.join(&self.ifs_separator())),
+            "@" => Some(self.positional.join(" ")),
+            _ => match name.parse::<usize>() {
+                Ok(0) => None,
+                Ok(n) => self.positional.get(n - 1).cloned(),
+                Err(_) => self.vars.value(name).map(ToString::to_string),
+            },
+        }
+    }
+
+    /// The separator used when joining `"$*"`, the first character of `IFS`
+    fn ifs_separator(&self) -> String {
+        match self.param("IFS") {
+            Some(ifs) => ifs.chars().next().map(String::from).unwrap_or_default(),
+            None => " ".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(ifs: Option<&str>, parts: &[&str]) -> Vec<String> {
+        let mut fields = Fields::new(ifs.map(String::from));
+        for part in parts {
+            fields.push_split(part);
+        }
+        fields.finish_field();
+        fields.done
+    }
+
+    #[test]
+    fn split_on_whitespace() {
+        assert_eq!(
+            split(Some(DEFAULT_IFS), &["  a \t b\n\nc  "]),
+            ["a", "b", "c"]
+        );
+        assert_eq!(split(Some(DEFAULT_IFS), &["   "]), Vec::<String>::new());
+        assert_eq!(split(Some(DEFAULT_IFS), &[""]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn split_on_non_whitespace() {
+        // every non-whitespace IFS character ends a field, even an empty one, but a trailing one adds no field
+        assert_eq!(split(Some(":"), &["a::b:"]), ["a", "", "b"]);
+        assert_eq!(split(Some(":"), &[":a"]), ["", "a"]);
+        // whitespace around a non-whitespace IFS character belongs to the same separator
+        assert_eq!(split(Some(": "), &["a : b  c"]), ["a", "b", "c"]);
+        assert_eq!(split(Some(": "), &[" : a"]), ["", "a"]);
+        // whitespace that is not in IFS is kept
+        assert_eq!(split(Some(":"), &[" a :b"]), [" a ", "b"]);
+    }
+
+    #[test]
+    fn split_disabled() {
+        assert_eq!(split(Some(""), &["a b"]), ["a b"]);
+        assert_eq!(split(None, &["a b", ""]), ["a b"]);
+    }
+
+    #[test]
+    fn split_joins_adjacent_text() {
+        let mut fields = Fields::new(Some(DEFAULT_IFS.into()));
+        fields.push_str("pre");
+        fields.push_split("a b ");
+        fields.push_str("post");
+        fields.finish_field();
+        assert_eq!(fields.done, ["prea", "b", "post"]);
+        // a quoted empty string is a field of its own
+        let mut fields = Fields::new(Some(DEFAULT_IFS.into()));
+        fields.push_str("");
+        fields.finish_field();
+        assert_eq!(fields.done, [""]);
+    }
+}
@@ -0,0 +1,43 @@
+use std::{iter::Peekable, os::fd::OwnedFd};
+
+use crate::{
+    parser::{Function, Token},
+    shell::{Shell, error::ShellError, pipeline, redirect},
+};
+
+pub(crate) fn handle_function<'a, I>(
+    function: &Function,
+    args: &[String],
+    token_iter: &mut Peekable<I>,
+    prev_command_output: Option<String>,
+    prev_stdout: Option<OwnedFd>,
+    shell: &mut Shell,
+) -> Result<(), ShellError>
+where
+    I: Iterator<Item = &'a Token>,
+{
+    // a redirection applies to everything the function runs, so the shell's own output goes to the file meanwhile
+    let _redirection = match token_iter.next_if(|token| matches!(token, Token::Redirect(_))) {
+        Some(Token::Redirect(redirect_symb)) => Some(redirect::redirect_function_output(
+            redirect_symb,
+            token_iter,
+        )?),
+        _ => None,
+    };
+
+    if prev_command_output.is_none()
+        && prev_stdout.is_none()
+        && !matches!(token_iter.peek(), Some(Token::Pipe))
+    {
+        shell.call_function(function, args);
+        return Ok(());
+    }
+    pipeline::run_pipeline_function(
+        function,
+        args,
+        prev_command_output,
+        prev_stdout,
+        token_iter,
+        shell,
+    )
+}
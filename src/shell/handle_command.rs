@@ -1,28 +1,35 @@
 use std::{iter::Peekable, str::FromStr as _};
 
-use rustyline::history::FileHistory;
-
 use crate::{
     commands::{Builtin, find_exec_file},
     parser::Token,
-    shell::{builtin_exec::handle_builtin, error::ShellError, exec::handle_external_exec},
+    shell::{
+        Shell, builtin_exec::handle_builtin, error::ShellError, exec::handle_external_exec,
+        function_exec::handle_function,
+    },
 };
 
 pub fn handle_command<'a, I>(
     cmd_str: &str,
     args: &[String],
     token_iter: &mut Peekable<I>,
-    history: &mut FileHistory,
+    shell: &mut Shell,
 ) -> Result<(), ShellError>
 where
     I: Iterator<Item = &'a Token>,
 {
     if let Ok(builtin) = Builtin::from_str(cmd_str) {
-        handle_builtin(builtin, args, token_iter, None, None, history)?;
-    } else if find_exec_file(cmd_str)?.is_some() {
-        handle_external_exec(cmd_str, args, token_iter, None, None, history)?;
+        // only an external command replaces a background child, builtins and functions run in the child itself
+        shell.exec_external = false;
+        handle_builtin(builtin, args, token_iter, None, None, shell)?;
+    } else if let Some(function) = shell.functions.get(cmd_str).cloned() {
+        shell.exec_external = false;
+        handle_function(&function, args, token_iter, None, None, shell)?;
+    } else if find_exec_file(cmd_str, shell.vars.value("PATH"))?.is_some() {
+        handle_external_exec(cmd_str, args, token_iter, None, None, shell)?;
     } else {
-        println!("{cmd_str}: command not found");
+        println!("{}{cmd_str}: command not found", shell.error_prefix());
+        shell.last_status = 127;
     }
     Ok(())
 }
@@ -0,0 +1,43 @@
+use crate::shell::{Flow, Shell};
+
+impl Shell {
+    /// Run the `precmd` function and `PROMPT_COMMAND` before a prompt is shown
+    /// `precmd` gets the status of the last command as `$1`, `PROMPT_COMMAND` sees it as `$?` and may be an array of commands
+    pub(crate) fn run_prompt_hooks(&mut self) {
+        let status = self.last_status;
+        self.call_hook("precmd", &[status.to_string()]);
+
+        let commands: Vec<String> = self
+            .vars
+            .get("PROMPT_COMMAND")
+            .and_then(|var| var.value.as_ref())
+            .map(|value| value.values().into_iter().map(String::from).collect())
+            .unwrap_or_default();
+        for command in commands {
+            if self.flow == Some(Flow::Exit) {
+                return;
+            }
+            self.last_status = status;
+            self.run_string(&command);
+        }
+        // the hooks must not change `$?` for the prompt or the next command
+        if self.flow != Some(Flow::Exit) {
+            self.last_status = status;
+        }
+    }
+
+    /// Run the `preexec` function with the command line about to be executed as `$1`
+    pub(crate) fn run_preexec_hook(&mut self, line: &str) {
+        let status = self.last_status;
+        self.call_hook("preexec", &[line.to_string()]);
+        if self.flow != Some(Flow::Exit) {
+            self.last_status = status;
+        }
+    }
+
+    fn call_hook(&mut self, name: &str, args: &[String]) {
+        if let Some(function) = self.functions.get(name).cloned() {
+            self.call_function(&function, args);
+        }
+    }
+}
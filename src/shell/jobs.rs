@@ -0,0 +1,814 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    process::Child,
+};
+
+use nix::{
+    errno::Errno,
+    sys::{
+        signal::{Signal, kill, killpg},
+        termios::Termios,
+        wait::{WaitPidFlag, WaitStatus, waitpid},
+    },
+    unistd::{ForkResult, Pid, dup2_stdin, fork, setpgid},
+};
+
+use crate::{
+    parser::Command,
+    shell::{Shell, ShoptOption, exec::status_code, signals},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What a job is doing, as shown by `jobs`
+pub(crate) enum JobState {
+    Running,
+    /// Stopped by the given signal until it is continued with `fg` or `bg`
+    Stopped(Signal),
+    /// Exited with the given status
+    Done(i32),
+    /// Terminated by a signal, possibly dumping core
+    Killed(Signal, bool),
+}
+
+impl JobState {
+    pub(crate) fn is_finished(self) -> bool {
+        matches!(self, JobState::Done(_) | JobState::Killed(..))
+    }
+
+    /// The status `$?` reports for a job in this state
+    pub(crate) fn status(self) -> i32 {
+        match self {
+            JobState::Running => 0,
+            JobState::Done(status) => status,
+            JobState::Stopped(signal) | JobState::Killed(signal, _) => 128 + signal as i32,
+        }
+    }
+
+    /// The state a job is in after `waitpid` reported `status` for it, if it changed
+    fn from_wait_status(status: WaitStatus) -> Option<Self> {
+        match status {
+            WaitStatus::Exited(_, status) => Some(JobState::Done(status)),
+            WaitStatus::Signaled(_, signal, core_dumped) => {
+                Some(JobState::Killed(signal, core_dumped))
+            }
+            WaitStatus::Stopped(_, signal) => Some(JobState::Stopped(signal)),
+            WaitStatus::Continued(_) => Some(JobState::Running),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Running => write!(f, "Running"),
+            JobState::Stopped(Signal::SIGSTOP) => write!(f, "Stopped (signal)"),
+            JobState::Stopped(Signal::SIGTTIN) => write!(f, "Stopped (tty input)"),
+            JobState::Stopped(Signal::SIGTTOU) => write!(f, "Stopped (tty output)"),
+            JobState::Stopped(_) => write!(f, "Stopped"),
+            JobState::Done(0) => write!(f, "Done"),
+            JobState::Done(status) => write!(f, "Exit {status}"),
+            JobState::Killed(signal, core_dumped) => {
+                write!(f, "{}", signal_description(*signal))?;
+                if *core_dumped {
+                    write!(f, " (core dumped)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The description bash prints for a job killed by `signal`
+fn signal_description(signal: Signal) -> &'static str {
+    match signal {
+        Signal::SIGHUP => "Hangup",
+        Signal::SIGINT => "Interrupt",
+        Signal::SIGQUIT => "Quit",
+        Signal::SIGILL => "Illegal instruction",
+        Signal::SIGABRT => "Aborted",
+        Signal::SIGFPE => "Floating point exception",
+        Signal::SIGKILL => "Killed",
+        Signal::SIGSEGV => "Segmentation fault",
+        Signal::SIGPIPE => "Broken pipe",
+        Signal::SIGALRM => "Alarm clock",
+        Signal::SIGTERM => "Terminated",
+        Signal::SIGUSR1 => "User defined signal 1",
+        Signal::SIGUSR2 => "User defined signal 2",
+        other => other.as_str(),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// One of the processes making up a job
+pub(crate) struct Process {
+    pub(crate) pid: Pid,
+    pub(crate) state: JobState,
+}
+
+impl Process {
+    pub(crate) fn new(pid: Pid) -> Self {
+        Self {
+            pid,
+            state: JobState::Running,
+        }
+    }
+}
+
+/// The state of a job made of `processes`: stopped when any of them is, running while any of them runs,
+/// otherwise finished like the last one, as the status of a pipeline is that of its last command
+fn combined_state(processes: &[Process]) -> JobState {
+    let states = || processes.iter().map(|process| process.state);
+    states()
+        .find(|state| matches!(state, JobState::Stopped(_)))
+        .or_else(|| states().find(|&state| state == JobState::Running))
+        .or_else(|| states().next_back())
+        .unwrap_or(JobState::Done(0))
+}
+
+#[derive(Debug, Clone)]
+/// A command running in children of the shell, in the background or stopped
+pub(crate) struct Job {
+    /// number of the job, as in `%1`
+    pub(crate) id: usize,
+    /// the processes of the job, the first one leads its process group
+    pub(crate) processes: Vec<Process>,
+    /// the process group of the job when it runs under job control
+    pub(crate) pgid: Option<Pid>,
+    /// the command as listed by `jobs`
+    pub(crate) command: String,
+    pub(crate) state: JobState,
+    /// whether the state changed since the job was last reported
+    pub(crate) changed: bool,
+    /// set by `disown -h` to spare the job the `SIGHUP` sent when the shell exits
+    pub(crate) nohup: bool,
+    /// terminal modes the job left when it stopped in the foreground, restored when it is continued there
+    pub(crate) modes: Option<Termios>,
+}
+
+impl Job {
+    /// The process `jobs -l` and `jobs -p` show for the job
+    pub(crate) fn pid(&self) -> Pid {
+        self.processes
+            .first()
+            .map_or(Pid::from_raw(0), |process| process.pid)
+    }
+
+    /// Record that `signal` continued every process of the job that had stopped
+    pub(crate) fn set_running(&mut self) {
+        for process in &mut self.processes {
+            if matches!(process.state, JobState::Stopped(_)) {
+                process.state = JobState::Running;
+            }
+        }
+        self.state = combined_state(&self.processes);
+    }
+
+    /// Derive the job's state from its processes, marking it as changed, returns whether the job just stopped
+    /// like in bash, a job continuing is not worth a notification
+    fn refresh_state(&mut self) -> bool {
+        let state = combined_state(&self.processes);
+        if state == self.state {
+            return false;
+        }
+        self.state = state;
+        self.changed = state != JobState::Running;
+        matches!(state, JobState::Stopped(_))
+    }
+
+    /// Send `signal` to the job's process group, or to its process when it has no group of its own
+    /// without a signal this only checks that the processes still exist
+    pub(crate) fn signal(&self, signal: impl Into<Option<Signal>>) -> nix::Result<()> {
+        match self.pgid {
+            Some(pgid) => killpg(pgid, signal),
+            None => kill(self.pid(), signal),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The foreground pipeline being run, which becomes a job if it is stopped
+pub(crate) struct Pipeline {
+    /// the command line, as `jobs` will list it
+    pub(crate) command: String,
+    /// process group of the pipeline under job control, started by its first process
+    pub(crate) group: Option<Pid>,
+    pub(crate) processes: Vec<Process>,
+    /// the signal that stopped the pipeline, after which its remaining processes are not waited for
+    pub(crate) stopped: Option<Signal>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(command: String) -> Self {
+        Self {
+            command,
+            group: None,
+            processes: vec![],
+            stopped: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// The jobs of the shell together with the order they were last started in, which decides the current job
+pub(crate) struct JobTable {
+    jobs: Vec<Job>,
+    /// ids from least to most recently started, the last one is the current job `%+` and the one before it `%-`
+    recency: Vec<usize>,
+}
+
+impl JobTable {
+    /// Add a job made of `processes`, numbered one higher than the highest job number in use
+    pub(crate) fn add(
+        &mut self,
+        processes: Vec<Process>,
+        group: Option<Pid>,
+        command: String,
+    ) -> &mut Job {
+        let id = self.jobs.last().map_or(1, |job| job.id + 1);
+        self.recency.push(id);
+        let state = combined_state(&processes);
+        self.jobs.push(Job {
+            id,
+            processes,
+            pgid: group,
+            command,
+            state,
+            changed: false,
+            nohup: false,
+            modes: None,
+        });
+        let last = self.jobs.len() - 1;
+        &mut self.jobs[last]
+    }
+
+    pub(crate) fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// The current job `%+`, the one most recently started, stopped or continued
+    pub(crate) fn current(&self) -> Option<usize> {
+        self.recency.last().copied()
+    }
+
+    /// The previous job `%-`
+    pub(crate) fn previous(&self) -> Option<usize> {
+        self.recency.iter().rev().nth(1).copied()
+    }
+
+    /// Make job `id` the current job
+    pub(crate) fn touch(&mut self, id: usize) {
+        self.recency.retain(|&recent| recent != id);
+        self.recency.push(id);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) -> Option<Job> {
+        self.recency.retain(|&recent| recent != id);
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.jobs.clear();
+        self.recency.clear();
+    }
+
+    /// The mark `jobs` shows next to the job, `+` for the current job, `-` for the previous one
+    pub(crate) fn mark(&self, id: usize) -> char {
+        match self.recency.iter().rev().position(|&recent| recent == id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        }
+    }
+
+    /// Check without blocking whether jobs have finished, stopped or continued, recording how they did
+    pub(crate) fn update(&mut self) {
+        let flags = WaitPidFlag::WNOHANG | WaitPidFlag::WUNTRACED | WaitPidFlag::WCONTINUED;
+        let mut stopped = vec![];
+        for job in self.jobs.iter_mut().filter(|job| !job.state.is_finished()) {
+            for process in &mut job.processes {
+                if process.state.is_finished() {
+                    continue;
+                }
+                match waitpid(process.pid, Some(flags)) {
+                    Ok(status) => {
+                        if let Some(state) = JobState::from_wait_status(status) {
+                            process.state = state;
+                        }
+                    }
+                    // the child is gone without us seeing its status, which is unknown like in bash
+                    Err(Errno::ECHILD) => process.state = JobState::Done(127),
+                    // an interrupted or failed check leaves the process as it was, to be checked again later
+                    Err(_) => {}
+                }
+            }
+            if job.refresh_state() {
+                stopped.push(job.id);
+            }
+        }
+        for id in stopped {
+            self.touch(id);
+        }
+    }
+
+    /// Block until any child of the shell finishes or stops and record it for the job it belongs to
+    /// fails with `EINTR` when a signal interrupts the wait and `ECHILD` when there are no children left
+    pub(crate) fn wait_any(&mut self) -> nix::Result<()> {
+        let status = waitpid(None, Some(WaitPidFlag::WUNTRACED))?;
+        let (Some(pid), Some(state)) = (status.pid(), JobState::from_wait_status(status)) else {
+            return Ok(());
+        };
+        let Some(job) = self.find_process(pid) else {
+            return Ok(());
+        };
+        if let Some(process) = job.processes.iter_mut().find(|process| process.pid == pid) {
+            process.state = state;
+        }
+        if job.refresh_state() {
+            let id = job.id;
+            self.touch(id);
+        }
+        Ok(())
+    }
+
+    /// The job one of whose processes is `pid`
+    pub(crate) fn find_process(&mut self, pid: Pid) -> Option<&mut Job> {
+        self.jobs
+            .iter_mut()
+            .find(|job| job.processes.iter().any(|process| process.pid == pid))
+    }
+
+    /// Format a job the way `jobs` lists it, `long` adds its process id
+    pub(crate) fn format(&self, job: &Job, long: bool) -> String {
+        // bash pads the short form so the states line up with the long one's
+        let pid = if long {
+            format!(" {}", job.pid())
+        } else {
+            " ".into()
+        };
+        let command = if job.state == JobState::Running {
+            format!("{} &", job.command)
+        } else {
+            job.command.clone()
+        };
+        let state = job.state.to_string();
+        format!(
+            "[{}]{}{pid} {state:<24}{command}",
+            job.id,
+            self.mark(job.id)
+        )
+    }
+}
+
+impl Shell {
+    /// Run `command` in a forked copy of the shell without waiting for it, adding it to the job table
+    pub(crate) fn run_background(&mut self, command: &Command) {
+        // anything still buffered would otherwise be written by both processes
+        let _ = io::stdout().flush();
+        let job_control = self.job_control();
+        // a simple command is executed by the child itself, rather than by a process the child waits for
+        let exec_external = matches!(command, Command::Simple { words, .. }
+            if !words.iter().any(|word| word == "|") && self.expand_alias(words).is_none());
+        // SAFETY: background jobs are processes rather than threads and the shell never spawns a thread of its
+        // own, so the child inherits no lock that another thread was holding when it runs or execs the command
+        #[allow(unsafe_code)]
+        match unsafe { fork() } {
+            Ok(ForkResult::Child) => {
+                self.enter_subshell();
+                if job_control {
+                    // both processes set the group so it exists whichever of them runs first
+                    let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+                    signals::default_stop_signals();
+                } else {
+                    // without job control a background job must not compete with the shell for its input or
+                    // die from the Ctrl-C meant for the foreground
+                    signals::ignore_interrupts();
+                    if let Ok(null) = File::open("/dev/null") {
+                        let _ = dup2_stdin(&null);
+                    }
+                }
+                self.exec_external = exec_external;
+                self.execute(std::slice::from_ref(command));
+                self.run_exit_trap();
+                let _ = io::stdout().flush();
+                std::process::exit(self.last_status);
+            }
+            Ok(ForkResult::Parent { child }) => {
+                let pgid = job_control.then(|| {
+                    let _ = setpgid(child, child);
+                    child
+                });
+                let job = self
+                    .jobs
+                    .add(vec![Process::new(child)], pgid, command.to_string());
+                if self.interactive {
+                    eprintln!("[{}] {child}", job.id);
+                }
+                self.last_background_pid = child.as_raw().try_into().ok();
+                self.last_status = 0;
+            }
+            Err(e) => {
+                eprintln!("{}fork: {e}", self.error_prefix());
+                self.last_status = 1;
+            }
+        }
+    }
+
+    /// Turn a forked copy of the shell into a subshell, which has no prompt, no jobs and none of the traps of
+    /// its parent besides ignored signals
+    pub(crate) fn enter_subshell(&mut self) {
+        self.interactive = false;
+        self.jobs.clear();
+        self.pipeline = None;
+        self.interrupt_guard = None;
+        self.exec_external = false;
+        self.reset_traps();
+    }
+
+    /// Print a notification for every job that finished or stopped since the last prompt, forgetting finished ones
+    pub(crate) fn notify_jobs(&mut self) {
+        self.jobs.update();
+        let changed: Vec<_> = self
+            .jobs
+            .iter()
+            .filter(|job| job.changed)
+            .map(|job| (job.id, job.state, self.jobs.format(job, false)))
+            .collect();
+        for (id, state, line) in changed {
+            eprintln!("{line}");
+            if state.is_finished() {
+                self.jobs.remove(id);
+            } else if let Some(job) = self.jobs.get_mut(id) {
+                job.changed = false;
+            }
+        }
+    }
+
+    /// Wait for a process of the foreground pipeline and return its status
+    /// under job control the process may stop instead, the pipeline then becomes a job once it is finished with
+    /// and its remaining processes are not waited for
+    pub(crate) fn wait_child(&mut self, child: &mut Child) -> io::Result<i32> {
+        if !self.pipeline_has_group() {
+            return child.wait().map(status_code);
+        }
+        self.wait_pipeline_process(Pid::from_raw(child.id().try_into().unwrap_or_default()))
+    }
+
+    /// Wait for a subshell forked for a stage of the foreground pipeline and return its status, like `wait_child`
+    pub(crate) fn wait_forked_stage(&mut self, pid: Pid) -> io::Result<i32> {
+        if !self.pipeline_has_group() {
+            return wait_subshell(pid);
+        }
+        self.wait_pipeline_process(pid)
+    }
+
+    fn pipeline_has_group(&self) -> bool {
+        self.pipeline
+            .as_ref()
+            .is_some_and(|pipeline| pipeline.group.is_some())
+    }
+
+    /// Wait for the process `pid` of the foreground pipeline under job control, recording whether it stopped
+    fn wait_pipeline_process(&mut self, pid: Pid) -> io::Result<i32> {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return wait_subshell(pid);
+        };
+        if let Some(signal) = pipeline.stopped {
+            return Ok(JobState::Stopped(signal).status());
+        }
+        let state = wait_process(pid)?;
+        if let Some(process) = pipeline
+            .processes
+            .iter_mut()
+            .find(|process| process.pid == pid)
+        {
+            process.state = state;
+        }
+        match state {
+            JobState::Stopped(signal) => pipeline.stopped = Some(signal),
+            JobState::Killed(Signal::SIGINT, _) => signals::set_interrupted(),
+            _ => {}
+        }
+        Ok(state.status())
+    }
+
+    /// Take the terminal back from the foreground pipeline once the shell is done with it,
+    /// adding it to the job table when it was stopped
+    pub(crate) fn finish_pipeline(&mut self) {
+        let Some(pipeline) = self.pipeline.take() else {
+            return;
+        };
+        let Some(group) = pipeline.group else {
+            return;
+        };
+        let modes = self.take_terminal();
+        if let Some(signal) = pipeline.stopped {
+            let job = self
+                .jobs
+                .add(pipeline.processes, Some(group), pipeline.command);
+            job.modes = modes;
+            let id = job.id;
+            if let Some(job) = self.jobs.get(id) {
+                eprintln!("\n{}", self.jobs.format(job, false));
+            }
+            self.last_status = JobState::Stopped(signal).status();
+        }
+    }
+
+    /// Continue job `id` in the foreground, giving it the terminal until it finishes or stops again
+    pub(crate) fn foreground_job(&mut self, id: usize) {
+        let Some(job) = self.jobs.get_mut(id) else {
+            return;
+        };
+        let (group, modes) = (job.pgid, job.modes.take());
+        let stopped = matches!(job.state, JobState::Stopped(_));
+        job.set_running();
+        let mut processes = job.processes.clone();
+        if let Some(group) = group {
+            self.give_terminal(group, modes.as_ref());
+        }
+        if stopped && let Some(job) = self.jobs.get(id) {
+            let _ = job.signal(Signal::SIGCONT);
+        }
+
+        for process in processes
+            .iter_mut()
+            .filter(|process| !process.state.is_finished())
+        {
+            process.state = wait_process(process.pid).unwrap_or(JobState::Done(0));
+            if matches!(process.state, JobState::Stopped(_)) {
+                break;
+            }
+        }
+        let modes = if group.is_some() {
+            self.take_terminal()
+        } else {
+            None
+        };
+
+        let state = combined_state(&processes);
+        self.last_status = state.status();
+        if state.is_finished() {
+            self.jobs.remove(id);
+            match state {
+                JobState::Killed(Signal::SIGINT, _) => signals::set_interrupted(),
+                JobState::Killed(..) => eprintln!("{state}"),
+                _ => {}
+            }
+        } else if let Some(job) = self.jobs.get_mut(id) {
+            job.processes = processes;
+            job.state = state;
+            job.modes = modes;
+            self.jobs.touch(id);
+            if let Some(job) = self.jobs.get(id) {
+                eprintln!("\n{}", self.jobs.format(job, false));
+            }
+        }
+    }
+
+    /// Whether exiting should be held off once because jobs are stopped, printing a warning when it is
+    pub(crate) fn warn_stopped_jobs(&mut self) -> bool {
+        if !self.interactive || self.exit_warned {
+            return false;
+        }
+        self.jobs.update();
+        if !self
+            .jobs
+            .iter()
+            .any(|job| matches!(job.state, JobState::Stopped(_)))
+        {
+            return false;
+        }
+        eprintln!("There are stopped jobs.");
+        self.exit_warned = true;
+        true
+    }
+
+    /// Send `SIGHUP` to the jobs of an exiting interactive shell, except those marked with `disown -h`
+    /// every job gets it from a login shell with `huponexit`, otherwise only stopped jobs as nothing could continue
+    /// them any more, and stopped jobs are continued so they can act on it
+    pub(crate) fn hangup_jobs(&mut self) {
+        if !self.interactive {
+            return;
+        }
+        let hangup_all = self.login && self.shopt(ShoptOption::Huponexit);
+        self.jobs.update();
+        for job in self.jobs.iter().filter(|job| !job.nohup) {
+            let stopped = matches!(job.state, JobState::Stopped(_));
+            if stopped || (hangup_all && job.state == JobState::Running) {
+                let _ = job.signal(Signal::SIGHUP);
+            }
+            if stopped {
+                let _ = job.signal(Signal::SIGCONT);
+            }
+        }
+    }
+
+    /// Continue the stopped job `id` in the background
+    pub(crate) fn background_job(&mut self, id: usize) {
+        let Some(job) = self.jobs.get_mut(id) else {
+            return;
+        };
+        let _ = job.signal(Signal::SIGCONT);
+        job.set_running();
+        job.changed = false;
+        self.jobs.touch(id);
+    }
+}
+
+/// Wait for a subshell that is not a job and return its status, Ctrl-C killing it interrupts the shell as well
+pub(crate) fn wait_subshell(pid: Pid) -> io::Result<i32> {
+    let state = wait_process(pid)?;
+    if matches!(state, JobState::Killed(Signal::SIGINT, _)) {
+        signals::set_interrupted();
+    }
+    Ok(state.status())
+}
+
+/// Block until the child `pid` exits, is killed or stops
+fn wait_process(pid: Pid) -> io::Result<JobState> {
+    loop {
+        match waitpid(pid, Some(WaitPidFlag::WUNTRACED)) {
+            Ok(status) => {
+                if let Some(state) = JobState::from_wait_status(status) {
+                    return Ok(state);
+                }
+            }
+            Err(Errno::EINTR) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, state: JobState) -> Process {
+        Process {
+            pid: Pid::from_raw(pid),
+            state,
+        }
+    }
+
+    #[test]
+    fn state_descriptions() {
+        let described = |state: JobState| state.to_string();
+        assert_eq!(described(JobState::Running), "Running");
+        assert_eq!(described(JobState::Stopped(Signal::SIGTSTP)), "Stopped");
+        assert_eq!(
+            described(JobState::Stopped(Signal::SIGSTOP)),
+            "Stopped (signal)"
+        );
+        assert_eq!(
+            described(JobState::Stopped(Signal::SIGTTIN)),
+            "Stopped (tty input)"
+        );
+        assert_eq!(
+            described(JobState::Stopped(Signal::SIGTTOU)),
+            "Stopped (tty output)"
+        );
+        assert_eq!(described(JobState::Done(0)), "Done");
+        assert_eq!(described(JobState::Done(3)), "Exit 3");
+        assert_eq!(
+            described(JobState::Killed(Signal::SIGTERM, false)),
+            "Terminated"
+        );
+        assert_eq!(
+            described(JobState::Killed(Signal::SIGSEGV, true)),
+            "Segmentation fault (core dumped)"
+        );
+        assert_eq!(
+            described(JobState::Killed(Signal::SIGWINCH, false)),
+            "SIGWINCH"
+        );
+    }
+
+    #[test]
+    fn state_statuses() {
+        assert_eq!(JobState::Running.status(), 0);
+        assert_eq!(JobState::Done(3).status(), 3);
+        assert_eq!(JobState::Stopped(Signal::SIGTSTP).status(), 148);
+        assert_eq!(JobState::Killed(Signal::SIGKILL, false).status(), 137);
+        assert!(JobState::Killed(Signal::SIGINT, false).is_finished());
+        assert!(!JobState::Stopped(Signal::SIGTSTP).is_finished());
+    }
+
+    #[test]
+    fn states_from_wait_statuses() {
+        let pid = Pid::from_raw(1);
+        assert_eq!(
+            JobState::from_wait_status(WaitStatus::Exited(pid, 2)),
+            Some(JobState::Done(2))
+        );
+        assert_eq!(
+            JobState::from_wait_status(WaitStatus::Signaled(pid, Signal::SIGABRT, true)),
+            Some(JobState::Killed(Signal::SIGABRT, true))
+        );
+        assert_eq!(
+            JobState::from_wait_status(WaitStatus::Stopped(pid, Signal::SIGTTIN)),
+            Some(JobState::Stopped(Signal::SIGTTIN))
+        );
+        assert_eq!(
+            JobState::from_wait_status(WaitStatus::Continued(pid)),
+            Some(JobState::Running)
+        );
+        assert_eq!(JobState::from_wait_status(WaitStatus::StillAlive), None);
+    }
+
+    #[test]
+    fn update_marks_a_process_that_is_not_a_child_as_gone() {
+        let mut jobs = JobTable::default();
+        // init is never a child of the test, so waiting for it fails with `ECHILD`
+        let id = jobs
+            .add(vec![process(1, JobState::Running)], None, "init".into())
+            .id;
+        jobs.update();
+        assert_eq!(jobs.get(id).map(|job| job.state), Some(JobState::Done(127)));
+    }
+
+    #[test]
+    fn pipeline_state() {
+        let stopped = JobState::Stopped(Signal::SIGTSTP);
+        // any stopped process stops the job, any running one keeps it running
+        assert_eq!(
+            combined_state(&[
+                process(1, JobState::Done(0)),
+                process(2, stopped),
+                process(3, JobState::Running)
+            ]),
+            stopped
+        );
+        assert_eq!(
+            combined_state(&[process(1, JobState::Running), process(2, JobState::Done(1))]),
+            JobState::Running
+        );
+        // a finished pipeline has the status of its last command
+        assert_eq!(
+            combined_state(&[process(1, JobState::Done(1)), process(2, JobState::Done(0))]),
+            JobState::Done(0)
+        );
+        assert_eq!(combined_state(&[]), JobState::Done(0));
+
+        let mut job = JobTable::default()
+            .add(
+                vec![process(1, stopped), process(2, stopped)],
+                None,
+                "a | b".into(),
+            )
+            .clone();
+        assert_eq!(job.state, stopped);
+        job.set_running();
+        assert_eq!(job.state, JobState::Running);
+    }
+
+    #[test]
+    fn job_listing() {
+        let mut jobs = JobTable::default();
+        jobs.add(
+            vec![process(100, JobState::Running)],
+            None,
+            "sleep 10".into(),
+        );
+        jobs.add(
+            vec![process(200, JobState::Stopped(Signal::SIGTSTP))],
+            None,
+            "vim".into(),
+        );
+        jobs.add(vec![process(300, JobState::Done(1))], None, "false".into());
+        let listing: Vec<_> = jobs.iter().map(|job| jobs.format(job, false)).collect();
+        assert_eq!(
+            listing,
+            [
+                "[1]   Running                 sleep 10 &",
+                "[2]-  Stopped                 vim",
+                "[3]+  Exit 1                  false",
+            ]
+        );
+        let Some(job) = jobs.get(2) else {
+            panic!("job 2 was added");
+        };
+        assert_eq!(
+            jobs.format(job, true),
+            "[2]- 200 Stopped                 vim"
+        );
+        // job numbers continue after the highest one in use
+        jobs.remove(3);
+        assert_eq!(jobs.add(vec![], None, "true".into()).id, 3);
+    }
+}
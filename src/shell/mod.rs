@@ -1,24 +1,59 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
     ffi::OsString,
     fs::{File, OpenOptions, read},
     io::{self, Write},
     path::Path,
+    rc::Rc,
 };
 
+use nix::sys::termios::Termios;
 use rustyline::{CompletionType, Config, Editor, error::ReadlineError, history::FileHistory};
 use thiserror::Error;
 
-use crate::{BUILTIN_COMMANDS, TrieCompleter};
+use crate::{
+    BUILTIN_COMMANDS, TrieCompleter,
+    parser::Function,
+    shell::{signals::InterruptGuard, special::init_shell_vars, vars::Variables},
+};
 
+mod alias;
+mod arith;
 mod builtin_exec;
+mod capture;
 mod error;
+mod eval;
 mod exec;
+mod expand;
+mod function_exec;
 mod handle_command;
+mod hooks;
+mod jobs;
+mod options;
 mod pipeline;
+mod prompt;
 mod redirect;
 mod repl;
+mod script;
+mod signals;
+mod source;
+mod special;
+mod startup;
+mod terminal;
+mod timing;
+mod traps;
+pub(crate) mod vars;
 
+pub(crate) use alias::is_valid_alias_name;
+pub(crate) use error::ShellError;
+pub(crate) use eval::Flow;
 pub(crate) use handle_command::handle_command;
+pub(crate) use jobs::{JobState, JobTable, Pipeline, Process};
+pub(crate) use options::ShoptOption;
+pub use options::{Input, RcFile, ShellOption, StartupOptions};
+pub(crate) use signals::{interrupted, parse_signal, pending_signal, signal_name};
+pub(crate) use timing::{cpu_times, format_duration};
+pub(crate) use traps::Trap;
 
 // TODO: create a proper error module
 #[derive(Debug, Error)]
@@ -70,15 +105,69 @@ pub enum ClawshExitError {
 ///     Ok(())
 /// }
 /// ```
+#[allow(clippy::struct_excessive_bools)]
 pub struct Shell {
-    rl: Editor<TrieCompleter, FileHistory>,
+    pub(crate) rl: Editor<TrieCompleter, FileHistory>,
     old_contents: Option<Vec<u8>>,
     history_file: Option<OsString>,
+    /// functions defined in this shell, looked up after builtins and before executables on $PATH
+    pub(crate) functions: HashMap<String, Rc<Function>>,
+    /// aliases replacing the first word of a simple command
+    pub(crate) aliases: BTreeMap<String, String>,
+    /// aliases being expanded by the commands currently running, which are not expanded again
+    pub(crate) active_aliases: Vec<String>,
+    /// shell variables, including the ones imported from the environment
+    pub(crate) vars: Variables,
+    /// depth of the scope holding the assignments preceding the command being run, if any
+    pub(crate) temporary_scope: Option<usize>,
+    /// positional parameters (`$1`, `$2`, ...) of the shell or of the current function call
+    pub(crate) positional: Vec<String>,
+    pub(crate) call_depth: usize,
+    /// number of files being executed by `source`, in which `return` is allowed
+    pub(crate) source_depth: usize,
+    /// file being executed, used to prefix error messages with the file and line
+    pub(crate) script_file: Option<String>,
+    /// exit status of the last command that was run
+    pub(crate) last_status: i32,
+    /// exit status of the last command substitution while expanding a simple command, which is the status of
+    /// a command made only of assignments
+    pub(crate) substitution_status: Option<i32>,
+    pub(crate) flow: Option<Flow>,
+    /// name the shell or script was invoked with, expanded by `$0`
+    pub(crate) arg0: String,
+    pub(crate) interactive: bool,
+    /// where the commands are read from, reported by `$-`
+    pub(crate) input: Input,
+    /// pid of the shell, which forked copies of it keep so `$$` expands to the same value in them
+    pub(crate) pid: u32,
+    /// login shells read the profile files on startup and the logout file on exit
+    pub(crate) login: bool,
+    /// options turned on with `set` or on the command line
+    pub(crate) options: BTreeSet<ShellOption>,
+    /// options turned on with `shopt -s`
+    pub(crate) shopts: BTreeSet<ShoptOption>,
+    pub(crate) jobs: JobTable,
+    /// set when exiting was held off once because of stopped jobs, so the next attempt in a row exits
+    pub(crate) exit_warned: bool,
+    /// set in a background child running a single simple command, whose external command then replaces the
+    /// child instead of running in a process of its own, so `$!` is the pid of the command itself
+    pub(crate) exec_external: bool,
+    /// the foreground pipeline being run
+    pub(crate) pipeline: Option<Pipeline>,
+    /// actions run by `trap` on signals and on the pseudo-signals `EXIT`, `DEBUG`, `ERR` and `RETURN`
+    pub(crate) traps: BTreeMap<Trap, String>,
+    /// number of trap actions being run, which other traps do not interrupt
+    pub(crate) trap_depth: usize,
+    /// keeps Ctrl-C from killing an interactive shell for as long as it runs
+    pub(crate) interrupt_guard: Option<InterruptGuard>,
+    /// terminal modes of the shell, saved while a job has the terminal and restored when the shell takes it back
+    pub(crate) terminal_modes: Option<Termios>,
+    /// pid of the last job started in the background, expanded by `$!`
+    pub(crate) last_background_pid: Option<u32>,
 }
 
 impl Shell {
-    #[allow(clippy::missing_panics_doc)]
-    /// Setup a new `Shell` instance
+    /// Setup a new interactive `Shell` instance
     ///
     /// # Errors
     /// - `ClawshSetupError::CreateHistFile` if the history file specified by `HISTFILE` environment variable does not exist and cannot be created
@@ -86,7 +175,32 @@ impl Shell {
     /// - `ClawshSetupError::CreateEditor` if the `rustyline::Editor` cannot be created for the REPL
     ///
     pub fn setup() -> Result<Self, ClawshSetupError> {
-        let history_file = std::env::var_os("HISTFILE");
+        Self::setup_with(&StartupOptions {
+            interactive: true,
+            input: Input::Stdin,
+            ..StartupOptions::default()
+        })
+    }
+
+    /// Setup a new `Shell` instance for running a script or command string, which never reads or writes `HISTFILE`
+    ///
+    /// # Errors
+    /// - `ClawshSetupError::CreateEditor` if the `rustyline::Editor` cannot be created
+    pub fn setup_non_interactive() -> Result<Self, ClawshSetupError> {
+        Self::setup_with(&StartupOptions::default())
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    /// Setup a new `Shell` instance as described by `options`, only interactive shells use `HISTFILE`
+    /// login shells then read `/etc/profile` and `~/.clawsh_profile`, other interactive shells `~/.clawshrc`
+    ///
+    /// # Errors
+    /// - `ClawshSetupError::CreateHistFile` if the history file specified by `HISTFILE` environment variable does not exist and cannot be created
+    /// - `ClawshSetupError::ReadHistFile` if the history file specified by `HISTFILE` environment variable cannot be read
+    /// - `ClawshSetupError::CreateEditor` if the `rustyline::Editor` cannot be created for the REPL
+    ///
+    pub fn setup_with(options: &StartupOptions) -> Result<Self, ClawshSetupError> {
+        let history_file = std::env::var_os("HISTFILE").filter(|_| options.interactive);
 
         if let Some(file_name) = history_file.as_ref()
             && !Path::new(&file_name).exists()
@@ -114,18 +228,76 @@ impl Shell {
             old_contents =
                 Some(read(file).map_err(|e| ClawshSetupError::ReadHistFile(file.clone(), e))?);
         }
-        Ok(Self {
+        let mut vars = Variables::from_env();
+        init_shell_vars(&mut vars);
+
+        let mut shell = Self {
             rl,
             old_contents,
             history_file,
-        })
+            functions: HashMap::new(),
+            aliases: BTreeMap::new(),
+            active_aliases: vec![],
+            vars,
+            temporary_scope: None,
+            positional: vec![],
+            call_depth: 0,
+            source_depth: 0,
+            script_file: None,
+            last_status: 0,
+            substitution_status: None,
+            flow: None,
+            arg0: std::env::args().next().unwrap_or_else(|| "clawsh".into()),
+            interactive: options.interactive,
+            input: options.input,
+            pid: std::process::id(),
+            login: options.login,
+            options: BTreeSet::new(),
+            shopts: BTreeSet::new(),
+            jobs: JobTable::default(),
+            exit_warned: false,
+            exec_external: false,
+            pipeline: None,
+            traps: BTreeMap::new(),
+            trap_depth: 0,
+            terminal_modes: None,
+            interrupt_guard: options.interactive.then(InterruptGuard::catch),
+            last_background_pid: None,
+        };
+        shell.init_job_control();
+        shell.read_startup_files(options);
+        Ok(shell)
+    }
+
+    /// Set the name of the shell or script, as expanded by `$0`
+    pub fn set_arg0(&mut self, name: String) {
+        self.arg0 = name;
     }
+
+    /// Exit status of the last command executed, which the shell exits with
+    #[must_use]
+    pub fn last_status(&self) -> i32 {
+        self.last_status
+    }
+
+    /// Set the positional parameters (`$1`, `$2`, ...) of the top-level shell, e.g. from the command line
+    pub fn set_positional_parameters(&mut self, args: Vec<String>) {
+        self.positional = args;
+    }
+
     /// Exit the shell writing history back to file specified by `HISTFILE` environment variable if it is set
     ///  if `HISTFILE` is not set, no history will be written and the function will return `Ok(())`
+    ///  a login shell first runs `~/.clawsh_logout`, then the `EXIT` trap runs unless `Shell::run_exit_trap` ran it
+    ///  already, and jobs are sent `SIGHUP` as described by `shopt huponexit`
     ///
     /// # Errors
     /// - `ClawshExitError::CouldNotOpenHistFile` if the history file specified by `HISTFILE` environment variable cannot be opened for writing during shell exit
-    pub fn exit(self) -> Result<(), ClawshExitError> {
+    pub fn exit(mut self) -> Result<(), ClawshExitError> {
+        if self.login {
+            self.read_logout_file();
+        }
+        self.run_exit_trap();
+        self.hangup_jobs();
         if let Some(history_file) = self.history_file.as_ref() {
             let mut file = OpenOptions::new()
                 .append(true)
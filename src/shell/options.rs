@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use crate::shell::Shell;
+
+#[derive(Debug, Clone, Default)]
+/// How the shell is started, usually decided by the command line
+pub struct StartupOptions {
+    /// Read commands with a prompt, line editing and persistent history
+    pub interactive: bool,
+    /// Act as a login shell
+    pub login: bool,
+    /// Startup file read by interactive shells
+    pub rc: RcFile,
+    /// Skip the startup files read by login shells
+    pub noprofile: bool,
+    /// Where the commands come from
+    pub input: Input,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Where the shell reads its commands from, `$-` reports `c` and `s` for the last two
+pub enum Input {
+    #[default]
+    /// A script file named on the command line
+    Script,
+    /// The string given to `-c`
+    Command,
+    /// Standard input, with a prompt when interactive
+    Stdin,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Which startup file an interactive shell reads, chosen with `--norc` and `--rcfile`
+pub enum RcFile {
+    #[default]
+    /// The default startup file
+    Default,
+    /// No startup file at all
+    None,
+    /// The given file instead of the default one
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Options changing how commands are run, set with `set -o name` or their single letter flag
+pub enum ShellOption {
+    /// `-e`: exit as soon as a command fails
+    Errexit,
+    /// `-n`: read commands without executing them, ignored by interactive shells
+    Noexec,
+    /// `-u`: report expanding an unset parameter as an error
+    Nounset,
+    /// `-x`: print each command to stderr before executing it
+    Xtrace,
+}
+
+impl ShellOption {
+    /// Every option, in the order `set -o` lists them
+    pub const ALL: [Self; 4] = [Self::Errexit, Self::Noexec, Self::Nounset, Self::Xtrace];
+
+    /// The option with the single letter `flag`, e.g. `e` for `errexit`
+    #[must_use]
+    pub fn from_flag(flag: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|option| option.flag() == flag)
+    }
+
+    /// The option called `name`, e.g. `errexit`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|option| option.name() == name)
+    }
+
+    /// The single letter flag of the option
+    #[must_use]
+    pub fn flag(self) -> char {
+        match self {
+            Self::Errexit => 'e',
+            Self::Noexec => 'n',
+            Self::Nounset => 'u',
+            Self::Xtrace => 'x',
+        }
+    }
+
+    /// The name of the option as given to `set -o`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Errexit => "errexit",
+            Self::Noexec => "noexec",
+            Self::Nounset => "nounset",
+            Self::Xtrace => "xtrace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Options set with `shopt -s name`, which have no single letter flag
+pub(crate) enum ShoptOption {
+    /// send `SIGHUP` to all jobs when an interactive login shell exits
+    Huponexit,
+}
+
+impl ShoptOption {
+    /// Every option, in the order `shopt` lists them
+    pub(crate) const ALL: [Self; 1] = [Self::Huponexit];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|option| option.name() == name)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Huponexit => "huponexit",
+        }
+    }
+}
+
+impl Shell {
+    /// Turn a shell option on or off, like `set -o name` and `set +o name`
+    pub fn set_option(&mut self, option: ShellOption, enable: bool) {
+        if enable {
+            self.options.insert(option);
+        } else {
+            self.options.remove(&option);
+        }
+    }
+
+    pub(crate) fn option(&self, option: ShellOption) -> bool {
+        self.options.contains(&option)
+    }
+
+    /// Turn a `shopt` option on or off
+    pub(crate) fn set_shopt(&mut self, option: ShoptOption, enable: bool) {
+        if enable {
+            self.shopts.insert(option);
+        } else {
+            self.shopts.remove(&option);
+        }
+    }
+
+    pub(crate) fn shopt(&self, option: ShoptOption) -> bool {
+        self.shopts.contains(&option)
+    }
+}
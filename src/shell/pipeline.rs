@@ -1,85 +1,47 @@
 use std::{
-    io::Write as _,
+    io::{self, Write as _},
     iter::Peekable,
-    process::{Child, Command, Stdio},
+    os::fd::OwnedFd,
+    process::{Command, Stdio},
     str::FromStr as _,
 };
 
-use rustyline::history::FileHistory;
+use nix::unistd::{ForkResult, dup2_stdin, dup2_stdout};
 
 use crate::{
     commands::Builtin,
-    parser::Token,
-    shell::{builtin_exec, error::ShellError, exec},
+    parser::{Function, Token},
+    shell::{Shell, builtin_exec, error::ShellError, exec, function_exec},
 };
 
 pub(crate) fn run_pipeline_builtin<'a, I>(
     builtin_out: String,
     token_iter: &mut Peekable<I>,
-    history: &mut FileHistory,
+    shell: &mut Shell,
 ) -> Result<(), ShellError>
 where
     I: Iterator<Item = &'a Token>,
 {
-    let cmd = match token_iter.next() {
-        Some(Token::Command(str)) => str,
-        Some(t) => return Err(ShellError::PipedIntoNonCommand(Some(t.to_owned())))?,
-        None => return Err(ShellError::PipedIntoNonCommand(None))?,
-    };
-
-    let mut next_args = vec![];
-    while let Some(Token::Arg(s)) = token_iter.peek() {
-        next_args.push(s.clone());
-        token_iter.next();
-    }
-
-    // create pipeline recursively
-    if let Ok(cmd) = Builtin::from_str(cmd) {
-        builtin_exec::handle_builtin(
-            cmd,
-            &next_args[..],
-            token_iter,
-            Some(builtin_out),
-            None,
-            history,
-        )?;
-    } else {
-        exec::handle_external_exec(
-            cmd,
-            &next_args,
-            token_iter,
-            Some(builtin_out),
-            None,
-            history,
-        )?;
-    }
-    Ok(())
+    run_next_stage(Some(builtin_out), None, token_iter, shell)
 }
 
 pub(crate) fn run_pipeline_external<'a, I>(
     mut command: Command,
-    prev_command: Option<&mut Child>,
     prev_command_output: Option<String>,
     token_iter: &mut Peekable<I>,
-    history: &mut FileHistory,
+    shell: &mut Shell,
 ) -> Result<(), ShellError>
 where
     I: Iterator<Item = &'a Token>,
 {
     command.stdout(Stdio::piped());
 
-    if let Some(prev) = prev_command
-        && let Some(stdout) = prev.stdout.take()
-    {
-        command.stdin(stdout);
-    }
-
     if prev_command_output.is_some() {
         command.stdin(Stdio::piped());
     }
 
-    let mut child = command
-        .spawn()
+    let mut child = shell
+        .spawn(&mut command)
         .map_err(|e| ShellError::CommandSpawnFailure {
             name: command.get_program().to_os_string(),
             source: e,
@@ -96,6 +58,91 @@ where
         }
     }
 
+    let stdout = child.stdout.take().map(OwnedFd::from);
+    run_next_stage(None, stdout, token_iter, shell)?;
+
+    match shell.wait_child(&mut child) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(ShellError::CommandWaitFailure(child, e)),
+    }
+}
+
+/// Run a function as a stage of a pipeline in a forked subshell, as bash does, so its output reaches the next
+/// stage while it is still running
+pub(crate) fn run_pipeline_function<'a, I>(
+    function: &Function,
+    args: &[String],
+    prev_command_output: Option<String>,
+    prev_stdout: Option<OwnedFd>,
+    token_iter: &mut Peekable<I>,
+    shell: &mut Shell,
+) -> Result<(), ShellError>
+where
+    I: Iterator<Item = &'a Token>,
+{
+    let output = match token_iter.peek() {
+        Some(Token::Pipe) => Some(io::pipe().map_err(ShellError::StdioRedirectFailure)?),
+        _ => None,
+    };
+    // the output of a builtin is written to the subshell's stdin by the shell
+    let input = match prev_command_output {
+        Some(_) => Some(io::pipe().map_err(ShellError::StdioRedirectFailure)?),
+        None => None,
+    };
+
+    let child = match shell.fork_stage().map_err(ShellError::SubshellFailure)? {
+        ForkResult::Child => {
+            // only the ends the function uses stay open, so the other stages see the pipes close
+            if let Some((reader, writer)) = input {
+                drop(writer);
+                let _ = dup2_stdin(&reader);
+            } else if let Some(stdin) = prev_stdout {
+                let _ = dup2_stdin(&stdin);
+            }
+            if let Some((reader, writer)) = output {
+                drop(reader);
+                let _ = dup2_stdout(&writer);
+            }
+            shell.call_function(function, args);
+            shell.run_exit_trap();
+            let _ = io::stdout().flush();
+            std::process::exit(shell.last_status);
+        }
+        ForkResult::Parent { child } => child,
+    };
+    drop(prev_stdout);
+
+    if let (Some(prev), Some((reader, mut writer))) = (prev_command_output, input) {
+        drop(reader);
+        // a function that does not read its input closes the pipe before all of it is written
+        let _ = writer.write_all(prev.as_bytes());
+    }
+
+    let Some((reader, writer)) = output else {
+        shell.last_status = shell
+            .wait_forked_stage(child)
+            .map_err(ShellError::SubshellFailure)?;
+        return Ok(());
+    };
+    drop(writer);
+    token_iter.next();
+    run_next_stage(None, Some(reader.into()), token_iter, shell)?;
+    shell
+        .wait_forked_stage(child)
+        .map_err(ShellError::SubshellFailure)?;
+    Ok(())
+}
+
+/// Run the stage after a `|`, which reads the output of a builtin or the read end of the previous stage's stdout
+fn run_next_stage<'a, I>(
+    prev_command_output: Option<String>,
+    prev_stdout: Option<OwnedFd>,
+    token_iter: &mut Peekable<I>,
+    shell: &mut Shell,
+) -> Result<(), ShellError>
+where
+    I: Iterator<Item = &'a Token>,
+{
     let cmd = match token_iter.next() {
         Some(Token::Command(str)) => str,
         Some(t) => return Err(ShellError::PipedIntoNonCommand(Some(t.to_owned())))?,
@@ -107,16 +154,37 @@ where
         next_args.push(s.clone());
         token_iter.next();
     }
+    // assignments preceding the first command of the pipeline only apply to that command
+    shell.end_temporary_assignments();
 
     // create pipeline recursively
     if let Ok(cmd) = Builtin::from_str(cmd) {
-        builtin_exec::handle_builtin(cmd, &next_args, token_iter, None, Some(&mut child), history)?;
+        builtin_exec::handle_builtin(
+            cmd,
+            &next_args,
+            token_iter,
+            prev_command_output,
+            prev_stdout,
+            shell,
+        )?;
+    } else if let Some(function) = shell.functions.get(cmd).cloned() {
+        function_exec::handle_function(
+            &function,
+            &next_args,
+            token_iter,
+            prev_command_output,
+            prev_stdout,
+            shell,
+        )?;
     } else {
-        exec::handle_external_exec(cmd, &next_args, token_iter, None, Some(&mut child), history)?;
+        exec::handle_external_exec(
+            cmd,
+            &next_args,
+            token_iter,
+            prev_command_output,
+            prev_stdout,
+            shell,
+        )?;
     }
-
-    child
-        .wait()
-        .map_err(|e| ShellError::CommandWaitFailure(child, e))?;
     Ok(())
 }
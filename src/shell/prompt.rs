@@ -0,0 +1,254 @@
+use std::{
+    env,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use nix::unistd::{Uid, User, gethostname};
+
+use crate::shell::Shell;
+
+// what `\[` and `\]` become before expansion, the same markers readline uses around non-printing characters
+const START_IGNORE: char = '\u{1}';
+const END_IGNORE: char = '\u{2}';
+
+impl Shell {
+    /// Expand the prompt stored in `var`, e.g. `PS1`, into the text rustyline measures and the text it prints
+    /// the measured text leaves out everything between `\[` and `\]`, such as colour escape sequences
+    pub(crate) fn prompt(&mut self, var: &str, default: &str) -> (String, String) {
+        let template = self.vars.value(var).unwrap_or(default).to_string();
+        let decoded = self.decode_prompt(&template);
+        // expanding the prompt must not change `$?` for the next command
+        let status = self.last_status;
+        let expanded = match self.expand_to_string(&format!("\"{decoded}\"")) {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                eprintln!("{e}");
+                template
+            }
+        };
+        self.last_status = status;
+
+        let mut visible = String::new();
+        let mut ignoring = false;
+        for c in expanded.chars() {
+            match c {
+                START_IGNORE => ignoring = true,
+                END_IGNORE => ignoring = false,
+                _ if !ignoring => visible.push(c),
+                _ => {}
+            }
+        }
+        let styled = expanded.replace([START_IGNORE, END_IGNORE], "");
+        (visible, styled)
+    }
+
+    /// Replace the backslash escapes of a prompt, quoting the result so it can be expanded as a double quoted word
+    fn decode_prompt(&self, template: &str) -> String {
+        let mut decoded = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {}
+                '"' => {
+                    decoded.push_str("\\\"");
+                    continue;
+                }
+                _ => {
+                    decoded.push(c);
+                    continue;
+                }
+            }
+            let value = match chars.next() {
+                Some('u') => user_name(),
+                Some('h') => host_name().split('.').next().unwrap_or_default().into(),
+                Some('H') => host_name(),
+                Some('w') => self.prompt_dir(false),
+                Some('W') => self.prompt_dir(true),
+                Some('$') if Uid::effective().is_root() => "#".into(),
+                Some('$') => "$".into(),
+                Some('t') => local_time(),
+                Some('j') => self.jobs.len().to_string(),
+                Some('?') => self.last_status.to_string(),
+                Some('s') => self.arg0.rsplit('/').next().unwrap_or_default().into(),
+                Some('n') => "\n".into(),
+                Some('e') => "\x1b".into(),
+                Some('a') => "\x07".into(),
+                // up to three octal digits give the character with that code, e.g. `\033` for escape
+                Some(digit @ '0'..='7') => {
+                    let mut code = digit.to_digit(8).unwrap_or_default();
+                    for _ in 0..2 {
+                        let Some(digit) = chars.next_if(|c| c.is_digit(8)) else {
+                            break;
+                        };
+                        code = code * 8 + digit.to_digit(8).unwrap_or_default();
+                    }
+                    char::from_u32(code).unwrap_or_default().into()
+                }
+                Some('[') => START_IGNORE.into(),
+                Some(']') => END_IGNORE.into(),
+                Some('\\') | None => "\\".into(),
+                // unknown escapes are kept as they are
+                Some(c) => format!("\\{c}"),
+            };
+            for c in value.chars() {
+                if matches!(c, '\\' | '$' | '`' | '"') {
+                    decoded.push('\\');
+                }
+                decoded.push(c);
+            }
+        }
+        decoded
+    }
+
+    /// The current directory for `\w`, with the home directory shortened to `~`, or only its last component for `\W`
+    fn prompt_dir(&self, basename: bool) -> String {
+        let Ok(cwd) = env::current_dir() else {
+            return String::new();
+        };
+        if let Some(home) = self.vars.value("HOME").filter(|home| !home.is_empty())
+            && let Ok(rest) = cwd.strip_prefix(home)
+        {
+            return match rest.to_str() {
+                Some("") => "~".into(),
+                _ if basename => file_name(&cwd),
+                _ => format!("~/{}", rest.display()),
+            };
+        }
+        if basename {
+            file_name(&cwd)
+        } else {
+            cwd.display().to_string()
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+fn user_name() -> String {
+    let uid = Uid::current();
+    match User::from_uid(uid) {
+        Ok(Some(user)) => user.name,
+        _ => uid.to_string(),
+    }
+}
+
+fn host_name() -> String {
+    gethostname()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The local time as `HH:MM:SS`
+#[allow(unsafe_code)]
+fn local_time() -> String {
+    let now: libc::time_t = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            elapsed.as_secs().try_into().unwrap_or_default()
+        });
+    // SAFETY: `tm` is plain data that `localtime_r` fills in, both pointers are valid for the whole call
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&raw const now, &raw mut tm);
+        tm
+    };
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(shell: &mut Shell, template: &str) -> (String, String) {
+        assert!(shell.vars.set("PS1", template).is_ok());
+        shell.prompt("PS1", "")
+    }
+
+    fn visible(shell: &mut Shell, template: &str) -> String {
+        prompt(shell, template).0
+    }
+
+    #[test]
+    fn user_and_host() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        assert_eq!(visible(&mut shell, "\\u"), user_name());
+        let host = host_name();
+        assert_eq!(visible(&mut shell, "\\H"), host);
+        assert_eq!(
+            visible(&mut shell, "\\h"),
+            host.split('.').next().unwrap_or_default()
+        );
+        let sign = if Uid::effective().is_root() { "#" } else { "$" };
+        assert_eq!(visible(&mut shell, "\\$ "), format!("{sign} "));
+    }
+
+    #[test]
+    fn working_directory() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        let Ok(cwd) = env::current_dir() else {
+            panic!("no current directory");
+        };
+        let name = file_name(&cwd);
+        assert!(shell.vars.set("HOME", "/nonexistent").is_ok());
+        assert_eq!(visible(&mut shell, "\\w"), cwd.display().to_string());
+        assert_eq!(visible(&mut shell, "\\W"), name);
+        assert!(shell.vars.set("HOME", &cwd.display().to_string()).is_ok());
+        assert_eq!(visible(&mut shell, "\\w \\W"), "~ ~");
+        if let Some(parent) = cwd.parent() {
+            assert!(
+                shell
+                    .vars
+                    .set("HOME", &parent.display().to_string())
+                    .is_ok()
+            );
+            assert_eq!(visible(&mut shell, "\\w"), format!("~/{name}"));
+            assert_eq!(visible(&mut shell, "\\W"), name);
+        }
+    }
+
+    #[test]
+    fn non_printing_sequences() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        assert_eq!(
+            prompt(&mut shell, "\\[\\e[1m\\]bold\\[\\033[0m\\] "),
+            ("bold ".into(), "\x1b[1mbold\x1b[0m ".into())
+        );
+    }
+
+    #[test]
+    fn octal_and_other_escapes() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        assert_eq!(visible(&mut shell, "\\101\\60\\0619"), "A019");
+        assert_eq!(visible(&mut shell, "a\\nb\\\\ \\q"), "a\nb\\ \\q");
+        shell.last_status = 3;
+        assert_eq!(visible(&mut shell, "\\? $? \\j"), "3 3 0");
+        assert_eq!(shell.last_status, 3);
+    }
+
+    #[test]
+    fn expansions_in_escapes_are_literal() {
+        let Ok(mut shell) = Shell::setup_non_interactive() else {
+            panic!("shell setup failed");
+        };
+        shell.arg0 = "/bin/$(echo no)`x`\"".into();
+        assert!(shell.vars.set("x", "value").is_ok());
+        assert_eq!(
+            visible(&mut shell, "\\s \"$x\" ${#x}"),
+            "$(echo no)`x`\" \"value\" 5"
+        );
+    }
+}
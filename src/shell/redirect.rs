@@ -6,7 +6,14 @@ use std::{
     process::{Command, Stdio},
 };
 
-use crate::{parser::Token, shell::error::ShellError};
+use crate::{
+    parser::Token,
+    shell::{
+        Shell,
+        capture::{StdioGuard, Stream},
+        error::ShellError,
+    },
+};
 
 pub(crate) fn redirect_builtin_output<'a, I>(
     redirect_symb: &str,
@@ -74,7 +81,46 @@ pub(crate) fn redirect_external<'a, I>(
     command: &mut Command,
     redirect_symb: &str,
     token_iter: &mut Peekable<I>,
-) -> Result<(), ShellError>
+    shell: &mut Shell,
+) -> Result<i32, ShellError>
+where
+    I: Iterator<Item = &'a Token>,
+{
+    match open_redirection(redirect_symb, token_iter)? {
+        (file, Stream::Stdout) => command.stdout(Stdio::from(file)),
+        (file, Stream::Stderr) => command.stderr(Stdio::from(file)),
+    };
+
+    let mut child = shell
+        .spawn(command)
+        .map_err(|e| ShellError::CommandSpawnFailure {
+            name: command.get_program().to_os_string(),
+            source: e,
+        })?;
+    match shell.wait_child(&mut child) {
+        Ok(status) => Ok(status),
+        Err(e) => Err(ShellError::CommandWaitFailure(child, e)),
+    }
+}
+
+/// Point the shell's stdout or stderr at the file of a redirection until the guard is dropped, so the output of
+/// a function and of every command it runs goes there
+pub(crate) fn redirect_function_output<'a, I>(
+    redirect_symb: &str,
+    token_iter: &mut Peekable<I>,
+) -> Result<StdioGuard, ShellError>
+where
+    I: Iterator<Item = &'a Token>,
+{
+    let (file, stream) = open_redirection(redirect_symb, token_iter)?;
+    StdioGuard::redirect(stream, file)
+}
+
+/// Open the file named after the redirection operator `redirect_symb`, together with the stream it replaces
+fn open_redirection<'a, I>(
+    redirect_symb: &str,
+    token_iter: &mut Peekable<I>,
+) -> Result<(File, Stream), ShellError>
 where
     I: Iterator<Item = &'a Token>,
 {
@@ -94,46 +140,27 @@ where
     let mut file_options = File::options();
     file_options.create(true).write(true);
 
-    match redirect_symb {
+    let stream = match redirect_symb {
         ">" | "1>" => {
             file_options.truncate(true);
-            let file = file_options
-                .open(&file_path)
-                .map_err(|e| ShellError::FailedToOpenFile(file_path.clone(), e))?;
-            command.stdout(Stdio::from(file));
+            Stream::Stdout
         }
         "2>" => {
             file_options.truncate(true);
-            let file = file_options
-                .open(&file_path)
-                .map_err(|e| ShellError::FailedToOpenFile(file_path.clone(), e))?;
-            command.stderr(Stdio::from(file));
+            Stream::Stderr
         }
         "2>>" => {
-            let file = file_options
-                .append(true)
-                .open(&file_path)
-                .map_err(|e| ShellError::FailedToOpenFile(file_path.clone(), e))?;
-            command.stderr(Stdio::from(file));
+            file_options.append(true);
+            Stream::Stderr
         }
         ">>" | "1>>" => {
-            let file = file_options
-                .append(true)
-                .open(&file_path)
-                .map_err(|e| ShellError::FailedToOpenFile(file_path.clone(), e))?;
-            command.stdout(Stdio::from(file));
+            file_options.append(true);
+            Stream::Stdout
         }
         _ => unreachable!("Unknown redirection operator"),
-    }
-
-    let mut child = command
-        .spawn()
-        .map_err(|e| ShellError::CommandSpawnFailure {
-            name: file_name.into(),
-            source: e,
-        })?;
-    child
-        .wait()
-        .map_err(|e| ShellError::CommandWaitFailure(child, e))?;
-    Ok(())
+    };
+    let file = file_options
+        .open(&file_path)
+        .map_err(|e| ShellError::FailedToOpenFile(file_path.clone(), e))?;
+    Ok((file, stream))
 }
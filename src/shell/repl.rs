@@ -3,17 +3,26 @@ use std::io::{self, Write};
 use rustyline::error::ReadlineError;
 
 use crate::{
-    parser::{Token, split_words, tokenize_input},
-    shell::{Shell, error::ShellError, handle_command},
+    parser::{error::ParseError, parse_script},
+    shell::{Flow, Shell, error::ShellError, signals},
 };
 
 impl Shell {
     #[allow(clippy::missing_panics_doc)]
-    /// Main REPL loop for the shell, continuously reading user input, parsing it, and executing commands until an exit condition is met (e.g. `exit` command, EOF, or interrupt)
+    /// Main REPL loop for the shell, continuously reading user input, parsing it, and executing commands until an exit condition is met (`exit` or EOF)
     /// internal errors during command handling are printed to stderr but do not exit the shell
     pub fn run(&mut self) {
-        loop {
-            let readline = self.rl.readline("$ ");
+        // `exit` in a startup file ends the shell before the first prompt
+        while self.flow != Some(Flow::Exit) {
+            signals::clear_interrupt();
+            self.run_pending_traps();
+            self.notify_jobs();
+            self.run_prompt_hooks();
+            if self.flow == Some(Flow::Exit) {
+                break;
+            }
+            let prompt = self.prompt("PS1", "$ ");
+            let readline = self.rl.readline(&prompt);
             match io::stdout().flush() {
                 Ok(()) => {}
                 Err(e) => {
@@ -22,18 +31,24 @@ impl Shell {
                 }
             }
 
-            let input = match readline {
-                Ok(line) => {
-                    #[allow(clippy::expect_used)]
-                    self.rl.add_history_entry(line.as_str())
-                    .expect("`add_history_entry` cannot error for filehistory due to how the trait function is implemented by rusytline");
+            let mut input = match readline {
+                // the line editor only sees Ctrl-C itself when it can put the terminal in raw mode, on other
+                // terminals the signal arrives while reading and the terminal already discarded the line
+                Ok(line) if signals::interrupted() => {
+                    signals::clear_interrupt();
+                    self.last_status = 130;
                     line
                 }
+                Ok(line) => line,
+                // Ctrl-C discards the line being edited
                 Err(ReadlineError::Interrupted) => {
-                    println!("CTRL-C");
-                    break;
+                    self.last_status = 130;
+                    continue;
                 }
                 Err(ReadlineError::Eof) => {
+                    if self.warn_stopped_jobs() {
+                        continue;
+                    }
                     println!("CTRL-D");
                     break;
                 }
@@ -43,34 +58,54 @@ impl Shell {
                 }
             };
 
-            let trimmed_input = input.trim_end();
-            let command_list = split_words(trimmed_input);
-
-            let Some(tokens) = tokenize_input(command_list) else {
-                continue;
+            // keep reading continuation lines while a quote, group or function body is left open
+            let commands = loop {
+                match parse_script(&input) {
+                    Ok(commands) => break Some(commands),
+                    Err(ParseError::Incomplete) => {
+                        let prompt = self.prompt("PS2", "> ");
+                        match self.rl.readline(&prompt) {
+                            Ok(line) => {
+                                input.push('\n');
+                                input.push_str(&line);
+                            }
+                            Err(ReadlineError::Interrupted) => {
+                                self.last_status = 130;
+                                break None;
+                            }
+                            Err(_) => break None,
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{}", ShellError::from(e));
+                        break None;
+                    }
+                }
             };
-            let mut token_iter = tokens.iter().peekable();
 
-            let Some(Token::Command(cmd_str)) = token_iter.next() else {
-                // this path should be unreachable as the first token is always a command token.
-                // if the input was empty then `tokenize_input` would have returned None already
-                unreachable!();
+            #[allow(clippy::expect_used)]
+            self.rl.add_history_entry(input.as_str())
+                .expect("`add_history_entry` cannot error for filehistory due to how the trait function is implemented by rusytline");
+
+            let Some(commands) = commands else {
+                continue;
             };
 
-            if cmd_str == "exit" {
-                break;
+            if !commands.is_empty() {
+                self.run_preexec_hook(&input);
             }
-
-            let mut args = vec![];
-            while let Some(Token::Arg(s)) = token_iter.peek() {
-                args.push(s.clone());
-                token_iter.next();
+            self.execute(&commands);
+            // `exit` right after the warning about stopped jobs exits anyway
+            if self.flow == Some(Flow::Exit) {
+                if self.warn_stopped_jobs() {
+                    self.flow = None;
+                }
+            } else {
+                self.exit_warned = false;
             }
-
-            let history = self.rl.history_mut();
-            match handle_command(cmd_str, &args, &mut token_iter, history) {
-                Ok(()) => {}
-                Err(e) => eprintln!("{e}"),
+            // the terminal echoed `^C` without a newline, the prompt goes on a line of its own
+            if signals::interrupted() {
+                println!();
             }
         }
     }
@@ -0,0 +1,65 @@
+use std::{io::BufRead, path::Path};
+
+use crate::{
+    parser::{error::ParseError, parse_script},
+    shell::{Flow, Shell, error::ShellError},
+};
+
+impl Shell {
+    /// Run the script at `path` without a prompt, with `$0` set to its path
+    /// a `#!` line at the top is an ordinary comment, so scripts can be run via a shebang
+    /// each command runs as soon as it is parsed, so a syntax error stops the script only where it occurs
+    pub fn run_script(&mut self, path: &Path) {
+        self.arg0 = path.display().to_string();
+        if let Err(e) = self.execute_file(path) {
+            eprintln!("{e}");
+            // like bash, a script that cannot be read gives 127 and one that cannot be parsed 2
+            self.last_status = match e {
+                ShellError::ReadScript(..) => 127,
+                _ => 2,
+            };
+        }
+    }
+
+    /// Run a single command string, as given to `-c`
+    pub fn run_string(&mut self, src: &str) {
+        match parse_script(src) {
+            Ok(commands) => self.execute(&commands),
+            Err(e) => {
+                eprintln!("{}", ShellError::from(e));
+                self.last_status = 2;
+            }
+        }
+    }
+
+    /// Read and run commands from `reader` without a prompt until EOF or `exit`, e.g. when stdin is not a terminal
+    /// each command runs as soon as it is complete rather than after the whole input is read
+    pub fn run_reader(&mut self, mut reader: impl BufRead) {
+        let mut input = String::new();
+        loop {
+            let read = match reader.read_line(&mut input) {
+                Ok(read) => read,
+                Err(e) => {
+                    eprintln!("{e}");
+                    self.last_status = 2;
+                    return;
+                }
+            };
+            match parse_script(&input) {
+                Ok(commands) => {
+                    self.execute(&commands);
+                    input.clear();
+                }
+                Err(ParseError::Incomplete) if read > 0 => continue,
+                Err(e) => {
+                    eprintln!("{}", ShellError::from(e));
+                    self.last_status = 2;
+                    return;
+                }
+            }
+            if read == 0 || self.flow == Some(Flow::Exit) {
+                return;
+            }
+        }
+    }
+}
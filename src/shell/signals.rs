@@ -0,0 +1,249 @@
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+
+/// Set by the `SIGINT` handler, checked between commands so an interrupt abandons the rest of the command line
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn record_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn discard_signal(_: libc::c_int) {}
+
+/// Signals caught for a trap that arrived since their trap last ran, indexed by signal number
+static PENDING: [AtomicBool; 32] = [const { AtomicBool::new(false) }; 32];
+
+extern "C" fn record_signal(signal: libc::c_int) {
+    if let Some(pending) = usize::try_from(signal).ok().and_then(|i| PENDING.get(i)) {
+        pending.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Whether `SIGINT` arrived, or a foreground command was killed by it, since the interrupt was last cleared
+pub(crate) fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+pub(crate) fn set_interrupted() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn clear_interrupt() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// Guard that keeps `SIGINT` and `SIGQUIT` from killing the shell, restoring their previous actions when dropped
+/// the signals are caught rather than ignored because caught signals go back to their default action in the
+/// commands the shell executes, so those still die from Ctrl-C
+pub(crate) struct InterruptGuard {
+    /// the signals with their previous action and the handler the guard installed
+    previous: Vec<(Signal, SigAction, extern "C" fn(libc::c_int))>,
+}
+
+impl InterruptGuard {
+    /// Record `SIGINT` as an interrupt of the running command line, as an interactive shell does
+    pub(crate) fn catch() -> Self {
+        Self::install(record_interrupt)
+    }
+
+    /// Let `SIGINT` pass while waiting for a foreground command, which decides by dying from it or not whether the
+    /// shell is interrupted
+    pub(crate) fn wait() -> Self {
+        Self::install(discard_signal)
+    }
+
+    fn install(on_interrupt: extern "C" fn(libc::c_int)) -> Self {
+        let handlers = [
+            (Signal::SIGINT, on_interrupt),
+            (
+                Signal::SIGQUIT,
+                discard_signal as extern "C" fn(libc::c_int),
+            ),
+        ];
+        let previous = handlers
+            .into_iter()
+            .filter_map(|(signal, handler)| {
+                let action = SigAction::new(
+                    SigHandler::Handler(handler),
+                    // without `SA_RESTART` a blocking `wait` returns early to notice the interrupt
+                    SaFlags::empty(),
+                    SigSet::empty(),
+                );
+                // SAFETY: the handlers only store to an atomic, which is async-signal-safe
+                #[allow(unsafe_code)]
+                let previous = unsafe { sigaction(signal, &action) }.ok()?;
+                // an ignored signal stays ignored, as in a background job without job control
+                if matches!(previous.handler(), SigHandler::SigIgn) {
+                    // SAFETY: ignoring a signal installs no handler code
+                    #[allow(unsafe_code)]
+                    let _ = unsafe { sigaction(signal, &previous) };
+                    return None;
+                }
+                Some((signal, previous, handler))
+            })
+            .collect();
+        Self { previous }
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        for (signal, action, handler) in &self.previous {
+            // SAFETY: the action being restored was installed before the guard existed
+            #[allow(unsafe_code)]
+            let Ok(current) = (unsafe { sigaction(*signal, action) }) else {
+                continue;
+            };
+            // a trap set while the guard was installed stays in place
+            if !matches!(current.handler(), SigHandler::Handler(current) if std::ptr::fn_addr_eq(current, *handler))
+            {
+                // SAFETY: the action put back was installed by `trap` a moment ago
+                #[allow(unsafe_code)]
+                let _ = unsafe { sigaction(*signal, &current) };
+            }
+        }
+    }
+}
+
+/// Ignore `SIGINT` and `SIGQUIT` for good, as background jobs without job control do so Ctrl-C only stops the
+/// foreground command, the commands they run inherit this
+pub(crate) fn ignore_interrupts() {
+    let action = SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty());
+    for signal in [Signal::SIGINT, Signal::SIGQUIT] {
+        // SAFETY: ignoring a signal installs no handler code
+        #[allow(unsafe_code)]
+        let _ = unsafe { sigaction(signal, &action) };
+    }
+}
+
+/// Signals the terminal uses to stop jobs, which the shell itself must not be stopped by
+const STOP_SIGNALS: [Signal; 3] = [Signal::SIGTSTP, Signal::SIGTTIN, Signal::SIGTTOU];
+
+/// Keep Ctrl-Z and reading or writing the terminal from the background from stopping a shell with job control
+pub(crate) fn ignore_stop_signals() {
+    set_stop_signals(SigHandler::SigIgn);
+}
+
+/// Give the stop signals their default action back in a child that runs a job
+/// only calls `sigaction`, so it can run between `fork` and `exec`
+pub(crate) fn default_stop_signals() {
+    set_stop_signals(SigHandler::SigDfl);
+}
+
+fn set_stop_signals(handler: SigHandler) {
+    let action = SigAction::new(handler, SaFlags::empty(), SigSet::empty());
+    for signal in STOP_SIGNALS {
+        // SAFETY: ignoring a signal or restoring its default action installs no handler code
+        #[allow(unsafe_code)]
+        let _ = unsafe { sigaction(signal, &action) };
+    }
+}
+
+/// Catch `signal` for a trap, recording it to be handled between commands
+/// like an interrupt it makes blocking waits return early
+pub(crate) fn catch_signal(signal: Signal) {
+    let action = SigAction::new(
+        SigHandler::Handler(record_signal),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    #[allow(unsafe_code)]
+    let _ = unsafe { sigaction(signal, &action) };
+}
+
+/// Ignore `signal`, as `trap '' signal` does, the commands the shell executes inherit this
+pub(crate) fn ignore_signal(signal: Signal) {
+    let action = SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty());
+    // SAFETY: ignoring a signal installs no handler code
+    #[allow(unsafe_code)]
+    let _ = unsafe { sigaction(signal, &action) };
+}
+
+/// Give `signal` back the action it has without a trap, which for `SIGINT` and `SIGQUIT` in a shell that catches
+/// interrupts is the one of `InterruptGuard::catch`
+pub(crate) fn reset_signal(signal: Signal, catch_interrupts: bool) {
+    let handler = match signal {
+        Signal::SIGINT if catch_interrupts => SigHandler::Handler(record_interrupt),
+        Signal::SIGQUIT if catch_interrupts => SigHandler::Handler(discard_signal),
+        _ => SigHandler::SigDfl,
+    };
+    let action = SigAction::new(handler, SaFlags::empty(), SigSet::empty());
+    // SAFETY: the handlers only store to an atomic, which is async-signal-safe
+    #[allow(unsafe_code)]
+    let _ = unsafe { sigaction(signal, &action) };
+}
+
+/// Record `signal` as pending as if it had been caught, e.g. for a foreground command killed by Ctrl-C
+pub(crate) fn set_pending(signal: Signal) {
+    if let Some(pending) = PENDING.get(signal as usize) {
+        pending.store(true, Ordering::SeqCst);
+    }
+}
+
+/// The first of the caught signals waiting for their trap to run, if any
+pub(crate) fn pending_signal() -> Option<Signal> {
+    Signal::iterator().find(|&signal| {
+        PENDING
+            .get(signal as usize)
+            .is_some_and(|pending| pending.load(Ordering::SeqCst))
+    })
+}
+
+/// Take the caught signals waiting for their trap to run, in order of their numbers
+pub(crate) fn take_pending() -> Vec<Signal> {
+    Signal::iterator()
+        .filter(|&signal| {
+            PENDING
+                .get(signal as usize)
+                .is_some_and(|pending| pending.swap(false, Ordering::SeqCst))
+        })
+        .collect()
+}
+
+/// Parse a signal given by number or by name, with or without the `SIG` prefix and in any case
+pub(crate) fn parse_signal(spec: &str) -> Option<Signal> {
+    if let Ok(number) = spec.parse::<i32>() {
+        return Signal::try_from(number).ok();
+    }
+    let name = spec.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    Signal::from_str(&format!("SIG{name}")).ok()
+}
+
+/// The name of `signal` without the `SIG` prefix, as `kill -l` prints it
+pub(crate) fn signal_name(signal: Signal) -> &'static str {
+    let name = signal.as_str();
+    name.strip_prefix("SIG").unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_by_number_and_name() {
+        assert_eq!(parse_signal("9"), Some(Signal::SIGKILL));
+        assert_eq!(parse_signal("15"), Some(Signal::SIGTERM));
+        for spec in ["HUP", "SIGHUP", "hup", "sigHup"] {
+            assert_eq!(parse_signal(spec), Some(Signal::SIGHUP), "{spec}");
+        }
+        assert_eq!(parse_signal("USR1"), Some(Signal::SIGUSR1));
+        for spec in ["0", "-1", "999", "", "SIG", "NOPE", "SIGSIGINT"] {
+            assert_eq!(parse_signal(spec), None, "{spec}");
+        }
+    }
+
+    #[test]
+    fn names_without_prefix() {
+        assert_eq!(signal_name(Signal::SIGINT), "INT");
+        assert_eq!(signal_name(Signal::SIGUSR2), "USR2");
+        for signal in Signal::iterator() {
+            assert_eq!(parse_signal(signal_name(signal)), Some(signal));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{fs, path::Path};
+
+use crate::{
+    parser::{error::ParseError, parse_script_at},
+    shell::{Flow, Shell, Trap, error::ShellError, signals},
+};
+
+impl Shell {
+    /// Execute the file at `path` in the current shell, with `args` as positional parameters if any are given
+    ///
+    /// # Errors
+    /// - `ShellError::ReadScript` if the file cannot be read
+    /// - `ShellError::Syntax` if the file cannot be parsed
+    pub(crate) fn source(&mut self, path: &Path, args: &[String]) -> Result<(), ShellError> {
+        let saved_positional =
+            (!args.is_empty()).then(|| std::mem::replace(&mut self.positional, args.to_vec()));
+        self.source_depth += 1;
+        let res = self.execute_file(path);
+        self.run_trap(Trap::Return);
+        self.source_depth -= 1;
+        if let Some(positional) = saved_positional {
+            self.positional = positional;
+        }
+        // `return` in a sourced file stops executing the file
+        if self.flow == Some(Flow::Return) {
+            self.flow = None;
+        }
+        res
+    }
+
+    /// Parse and execute the file at `path`, prefixing errors of its commands with the file name and line
+    ///
+    /// # Errors
+    /// - `ShellError::ReadScript` if the file cannot be read
+    /// - `ShellError::Syntax` if the file cannot be parsed, after the commands before the error ran
+    pub(crate) fn execute_file(&mut self, path: &Path) -> Result<(), ShellError> {
+        let file = path.display().to_string();
+        let src = fs::read_to_string(path).map_err(|e| ShellError::ReadScript(file.clone(), e))?;
+
+        let saved_file = self.script_file.replace(file.clone());
+        let saved_lineno = self.vars.dynamic.lineno;
+        let res = self.execute_source(&src).map_err(|error| {
+            let line = match error {
+                ParseError::UnexpectedToken(_, line) => line,
+                ParseError::Incomplete => src.lines().count(),
+            };
+            ShellError::Syntax {
+                file,
+                line,
+                source: error,
+            }
+        });
+        self.script_file = saved_file;
+        self.vars.dynamic.lineno = saved_lineno;
+        res
+    }
+
+    /// Execute `src` one complete command at a time like bash does, so the commands before a syntax error run
+    /// and a command can define an alias or function used by the commands after it
+    fn execute_source(&mut self, src: &str) -> Result<(), ParseError> {
+        let mut input = String::new();
+        let mut first_line = 1;
+        let mut lines = src.split_inclusive('\n').peekable();
+        while let Some(line) = lines.next() {
+            input.push_str(line);
+            // the parser takes a backslash at the end of its input as joining an empty line
+            if is_continued(line) && lines.peek().is_some() {
+                continue;
+            }
+            match parse_script_at(&input, first_line) {
+                Ok(commands) => self.execute(&commands),
+                Err(ParseError::Incomplete) if lines.peek().is_some() => continue,
+                Err(e) => return Err(e),
+            }
+            first_line += input.matches('\n').count();
+            input.clear();
+            if self.flow.is_some() || signals::interrupted() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prefix for error messages, `file: line n: ` while executing a file and empty otherwise
+    pub(crate) fn error_prefix(&self) -> String {
+        match &self.script_file {
+            Some(file) => format!("{file}: line {}: ", self.vars.dynamic.lineno),
+            None => String::new(),
+        }
+    }
+}
+
+/// Whether `line` ends in a backslash escaping its newline
+fn is_continued(line: &str) -> bool {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    (line.len() - line.trim_end_matches('\\').len()) % 2 == 1
+}
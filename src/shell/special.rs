@@ -0,0 +1,137 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::shell::{
+    Input, Shell,
+    arith::eval_arith,
+    error::ShellError,
+    vars::{Value, Variables},
+};
+
+// modulus and multiplier of the Park-Miller "minimal standard" generator
+const RANDOM_MODULUS: u64 = 2_147_483_647;
+const RANDOM_MULTIPLIER: u64 = 16_807;
+
+#[derive(Debug, Clone)]
+/// State behind the dynamic variables `RANDOM`, `SECONDS` and `LINENO`, whose values change as the shell runs
+pub(crate) struct DynamicVars {
+    random_state: Cell<u64>,
+    seconds_base: Instant,
+    /// line of the command being executed, expanded by `$LINENO`
+    pub(crate) lineno: usize,
+}
+
+impl DynamicVars {
+    pub(crate) fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let vars = Self {
+            random_state: Cell::new(1),
+            seconds_base: Instant::now(),
+            lineno: 0,
+        };
+        vars.seed_random(u64::from(nanos ^ std::process::id()));
+        vars
+    }
+
+    /// The value of the dynamic variable `name`, `None` for any other name
+    pub(crate) fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "RANDOM" => Some(self.random().to_string()),
+            "SECONDS" => Some(self.seconds().to_string()),
+            "LINENO" => Some(self.lineno.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The next value of `$RANDOM`, between 0 and 32767
+    pub(crate) fn random(&self) -> u64 {
+        let state = self.random_state.get() * RANDOM_MULTIPLIER % RANDOM_MODULUS;
+        self.random_state.set(state);
+        state & 0x7fff
+    }
+
+    /// Assigning to `RANDOM` seeds the generator so the following sequence is reproducible
+    pub(crate) fn seed_random(&self, seed: u64) {
+        // the state must stay in 1..RANDOM_MODULUS for the generator to work
+        self.random_state.set(seed % (RANDOM_MODULUS - 1) + 1);
+    }
+
+    /// The value of `$SECONDS`, the number of seconds since the shell started or since `SECONDS` was assigned
+    pub(crate) fn seconds(&self) -> u64 {
+        self.seconds_base.elapsed().as_secs()
+    }
+
+    pub(crate) fn set_seconds(&mut self, seconds: u64) {
+        let now = Instant::now();
+        self.seconds_base = now.checked_sub(Duration::from_secs(seconds)).unwrap_or(now);
+    }
+}
+
+impl Shell {
+    /// The value of a special parameter or dynamic variable, `None` if `name` is neither or the parameter is unset
+    pub(crate) fn special_param(&self, name: &str) -> Option<String> {
+        match name {
+            "$" => Some(self.pid.to_string()),
+            "!" => self.last_background_pid.map(|pid| pid.to_string()),
+            "0" => Some(self.arg0.clone()),
+            "-" => Some(self.option_flags()),
+            _ => self.vars.dynamic.value(name),
+        }
+    }
+
+    /// Handle an assignment to a dynamic variable, returning `false` if `name` is an ordinary variable
+    ///
+    /// # Errors
+    /// - `ShellError::Arithmetic` if the value is not a valid arithmetic expression
+    pub(crate) fn assign_dynamic(&mut self, name: &str, value: &str) -> Result<bool, ShellError> {
+        match name {
+            "RANDOM" => {
+                let seed = eval_arith(value, &self.vars)?;
+                self.vars.dynamic.seed_random(seed.unsigned_abs());
+            }
+            "SECONDS" => {
+                let seconds = eval_arith(value, &self.vars)?;
+                self.vars
+                    .dynamic
+                    .set_seconds(seconds.try_into().unwrap_or_default());
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The flags of the options currently set, as expanded by `$-`
+    pub(crate) fn option_flags(&self) -> String {
+        let mut flags: String = self.options.iter().map(|option| option.flag()).collect();
+        if self.interactive {
+            flags.push('i');
+        }
+        match self.input {
+            Input::Command => flags.push('c'),
+            Input::Stdin => flags.push('s'),
+            Input::Script => {}
+        }
+        flags
+    }
+}
+
+/// Set up the variables a shell maintains for its children, `SHLVL` counting how deeply shells are nested and `PPID`
+pub(crate) fn init_shell_vars(vars: &mut Variables) {
+    let level = vars
+        .value("SHLVL")
+        .and_then(|level| level.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+    let shlvl = vars.declare("SHLVL", false);
+    shlvl.value = Some(Value::Scalar((level + 1).to_string()));
+    shlvl.attrs.exported = true;
+
+    let ppid = vars.declare("PPID", false);
+    ppid.value = Some(Value::Scalar(
+        std::os::unix::process::parent_id().to_string(),
+    ));
+    ppid.attrs.readonly = true;
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+use crate::shell::{Flow, RcFile, Shell, StartupOptions};
+
+const SYSTEM_PROFILE: &str = "/etc/profile";
+
+impl Shell {
+    /// Read the startup files for the kind of shell described by `options`, like bash a login shell reads the
+    /// profile files and only an interactive shell that is not a login shell reads the rc file
+    pub(crate) fn read_startup_files(&mut self, options: &StartupOptions) {
+        if options.login {
+            if !options.noprofile {
+                self.read_startup_file(Path::new(SYSTEM_PROFILE));
+                if let Some(profile) = self.home_file(".clawsh_profile") {
+                    self.read_startup_file(&profile);
+                }
+            }
+        } else if options.interactive {
+            match &options.rc {
+                RcFile::Default => {
+                    if let Some(rc) = self.default_rc_file() {
+                        self.read_startup_file(&rc);
+                    }
+                }
+                // a file given explicitly is reported when it cannot be read
+                RcFile::File(rc) => self.run_startup_file(rc),
+                RcFile::None => {}
+            }
+        }
+    }
+
+    /// Run `~/.clawsh_logout` when a login shell exits
+    pub(crate) fn read_logout_file(&mut self) {
+        self.flow = None;
+        if let Some(logout) = self.home_file(".clawsh_logout") {
+            self.read_startup_file(&logout);
+        }
+    }
+
+    /// `~/.clawshrc`, falling back to `$XDG_CONFIG_HOME/clawsh/rc`
+    fn default_rc_file(&self) -> Option<PathBuf> {
+        if let Some(rc) = self.home_file(".clawshrc").filter(|rc| rc.is_file()) {
+            return Some(rc);
+        }
+        let config_home = match self.vars.value("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(self.vars.value("HOME")?).join(".config"),
+        };
+        Some(config_home.join("clawsh").join("rc"))
+    }
+
+    /// `name` in the home directory, `None` if `HOME` is not set
+    fn home_file(&self, name: &str) -> Option<PathBuf> {
+        let home = self.vars.value("HOME").filter(|home| !home.is_empty())?;
+        Some(Path::new(home).join(name))
+    }
+
+    /// Source a startup file if it exists, missing startup files are silently skipped
+    fn read_startup_file(&mut self, path: &Path) {
+        if path.is_file() {
+            self.run_startup_file(path);
+        }
+    }
+
+    fn run_startup_file(&mut self, path: &Path) {
+        if let Err(e) = self.source(path, &[]) {
+            eprintln!("{e}");
+        }
+        // `exit` in a startup file still exits the shell, anything else must not leak into the first command
+        if self.flow != Some(Flow::Exit) {
+            self.flow = None;
+        }
+    }
+}
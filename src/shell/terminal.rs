@@ -0,0 +1,155 @@
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Write},
+    os::unix::process::CommandExt,
+    process::{Child, Command},
+};
+
+use nix::{
+    sys::termios::{SetArg, Termios, tcgetattr, tcsetattr},
+    unistd::{ForkResult, Pid, fork, getpgrp, setpgid, tcsetpgrp},
+};
+
+use crate::shell::{Process, Shell, signals};
+
+impl Shell {
+    /// Whether jobs run in process groups of their own, which only interactive shells reading a terminal do
+    pub(crate) fn job_control(&self) -> bool {
+        self.interactive && io::stdin().is_terminal()
+    }
+
+    /// Put an interactive shell in a process group of its own and make it the foreground group of the terminal
+    pub(crate) fn init_job_control(&self) {
+        if self.job_control() {
+            signals::ignore_stop_signals();
+            // fails harmlessly when the shell already leads its session
+            let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+            set_foreground(getpgrp());
+        }
+    }
+
+    /// Spawn a process of the foreground pipeline
+    /// under job control the first process starts a new process group that gets the terminal and the rest join it
+    pub(crate) fn spawn(&mut self, command: &mut Command) -> io::Result<Child> {
+        if std::mem::take(&mut self.exec_external) {
+            let _ = io::stdout().flush();
+            // only returns if the command could not be executed
+            return Err(command.exec());
+        }
+        if !self.job_control() {
+            return command.spawn();
+        }
+        let group = self.pipeline.as_ref().map(|pipeline| pipeline.group);
+        let raw_group = group.flatten().map_or(0, Pid::as_raw);
+        // SAFETY: the hook only calls `setpgid`, `open`, `tcsetpgrp`, `close` and `sigaction`, which are
+        // async-signal-safe
+        #[allow(unsafe_code)]
+        unsafe {
+            command.pre_exec(move || {
+                if group.is_some() {
+                    libc::setpgid(0, raw_group);
+                }
+                // the leader takes the terminal as well, so its group has it before it runs whichever of the
+                // child and the shell gets to it first, the shell's stdin may already be replaced by a pipe
+                if group == Some(None) {
+                    let tty = libc::open(c"/dev/tty".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC);
+                    if tty >= 0 {
+                        libc::tcsetpgrp(tty, libc::getpgrp());
+                        libc::close(tty);
+                    }
+                }
+                signals::default_stop_signals();
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        self.join_pipeline(Pid::from_raw(child.id().try_into().unwrap_or_default()));
+        Ok(child)
+    }
+
+    /// Fork a subshell running a stage of the foreground pipeline, which under job control joins the pipeline's
+    /// process group the way a spawned process does
+    pub(crate) fn fork_stage(&mut self) -> io::Result<ForkResult> {
+        // anything still buffered would otherwise be written by both processes
+        let _ = io::stdout().flush();
+        let group = self
+            .pipeline
+            .as_ref()
+            .filter(|_| self.job_control())
+            .map(|pipeline| pipeline.group);
+        // SAFETY: the shell never starts a thread, so the child has all of the shell's state to itself and can run
+        // the function of its stage as ordinary Rust code
+        #[allow(unsafe_code)]
+        let fork = unsafe { fork() }?;
+        match fork {
+            ForkResult::Child => {
+                if let Some(group) = group {
+                    let _ = setpgid(Pid::from_raw(0), group.unwrap_or(Pid::from_raw(0)));
+                    // the leader takes the terminal itself, like a spawned leader does
+                    if group.is_none()
+                        && let Ok(tty) = File::open("/dev/tty")
+                    {
+                        let _ = tcsetpgrp(&tty, getpgrp());
+                    }
+                    signals::default_stop_signals();
+                }
+                self.enter_subshell();
+            }
+            ForkResult::Parent { child } => {
+                if group.is_some() {
+                    self.join_pipeline(child);
+                }
+            }
+        }
+        Ok(fork)
+    }
+
+    /// Add the process `pid` to the foreground pipeline under job control, giving the terminal to the group it
+    /// starts if it is the first one
+    fn join_pipeline(&mut self, pid: Pid) {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        // the group is set from both sides so it exists before the shell gives it the terminal
+        let leader = pipeline.group.is_none();
+        let group = *pipeline.group.get_or_insert(pid);
+        let _ = setpgid(pid, group);
+        pipeline.processes.push(Process::new(pid));
+        if leader {
+            self.give_terminal(group, None);
+        }
+    }
+
+    /// Make `group` the foreground process group of the terminal, remembering the shell's terminal modes first
+    /// `modes` are the modes the job had when it stopped, which it gets back when continued
+    pub(crate) fn give_terminal(&mut self, group: Pid, modes: Option<&Termios>) {
+        if !self.job_control() {
+            return;
+        }
+        self.terminal_modes = tcgetattr(io::stdin()).ok();
+        if let Some(modes) = modes {
+            let _ = tcsetattr(io::stdin(), SetArg::TCSADRAIN, modes);
+        }
+        set_foreground(group);
+    }
+
+    /// Make the shell's process group the foreground group again and restore the shell's terminal modes
+    /// returns the modes the job left the terminal in
+    pub(crate) fn take_terminal(&mut self) -> Option<Termios> {
+        if !self.job_control() {
+            return None;
+        }
+        let job_modes = tcgetattr(io::stdin()).ok();
+        set_foreground(getpgrp());
+        if let Some(modes) = &self.terminal_modes {
+            let _ = tcsetattr(io::stdin(), SetArg::TCSADRAIN, modes);
+        }
+        job_modes
+    }
+}
+
+/// Make `group` the foreground process group of the terminal on stdin
+/// the shell ignores `SIGTTOU`, so it can do this while it is in the background itself
+fn set_foreground(group: Pid) {
+    let _ = tcsetpgrp(io::stdin(), group);
+}
@@ -0,0 +1,166 @@
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use nix::sys::{
+    resource::{UsageWho, getrusage},
+    time::TimeValLike,
+};
+
+use crate::{parser::Command, shell::Shell};
+
+// bash's default, a blank line followed by the times in the long `1m2.345s` form
+const DEFAULT_TIMEFORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+const POSIX_TIMEFORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+
+/// User and system CPU time used by the shell itself or by its terminated and waited for children
+pub(crate) fn cpu_times(who: UsageWho) -> (Duration, Duration) {
+    let Ok(usage) = getrusage(who) else {
+        return (Duration::ZERO, Duration::ZERO);
+    };
+    let duration = |micros: i64| Duration::from_micros(micros.try_into().unwrap_or_default());
+    (
+        duration(usage.user_time().num_microseconds()),
+        duration(usage.system_time().num_microseconds()),
+    )
+}
+
+/// CPU time of the shell and its children together, children only count once they have been waited for
+fn total_cpu_times() -> (Duration, Duration) {
+    let (user, sys) = cpu_times(UsageWho::RUSAGE_SELF);
+    let (child_user, child_sys) = cpu_times(UsageWho::RUSAGE_CHILDREN);
+    (user + child_user, sys + child_sys)
+}
+
+impl Shell {
+    /// Run `command` and report the real, user and system time it took to stderr in the format of `TIMEFORMAT`
+    pub(crate) fn run_timed(&mut self, posix: bool, command: Option<&Command>) {
+        let start = Instant::now();
+        let (user_before, sys_before) = total_cpu_times();
+        if let Some(command) = command {
+            self.execute(std::slice::from_ref(command));
+        }
+        let real = start.elapsed();
+        let (user_after, sys_after) = total_cpu_times();
+
+        let format = if posix {
+            POSIX_TIMEFORMAT
+        } else {
+            self.vars.value("TIMEFORMAT").unwrap_or(DEFAULT_TIMEFORMAT)
+        };
+        // an empty `TIMEFORMAT` turns the report off
+        if !format.is_empty() {
+            let times = Times {
+                real,
+                user: user_after.saturating_sub(user_before),
+                sys: sys_after.saturating_sub(sys_before),
+            };
+            eprintln!("{}", times.format(format));
+        }
+    }
+}
+
+struct Times {
+    real: Duration,
+    user: Duration,
+    sys: Duration,
+}
+
+impl Times {
+    /// Expand the `%[precision][l]R`, `U`, `S`, `%P` and `%%` escapes of a `TIMEFORMAT` string
+    fn format(&self, format: &str) -> String {
+        let mut out = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            let precision = chars
+                .next_if(char::is_ascii_digit)
+                .and_then(|digit| digit.to_digit(10))
+                .map_or(3, |digit| digit.min(3) as usize);
+            let long = chars.next_if_eq(&'l').is_some();
+            let time = match chars.next() {
+                Some('R') => self.real,
+                Some('U') => self.user,
+                Some('S') => self.sys,
+                Some('P') => {
+                    let real = self.real.as_secs_f64();
+                    let cpu = (self.user + self.sys).as_secs_f64();
+                    let percent = if real > 0.0 { cpu / real * 100.0 } else { 0.0 };
+                    let _ = write!(out, "{percent:.precision$}");
+                    continue;
+                }
+                Some('%') => {
+                    out.push('%');
+                    continue;
+                }
+                // unknown escapes are printed as they are
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                    continue;
+                }
+                None => {
+                    out.push('%');
+                    break;
+                }
+            };
+            out.push_str(&format_duration(time, precision, long));
+        }
+        out
+    }
+}
+
+/// Format `time` in seconds with `precision` decimals, or as `1m2.345s` in the long format
+pub(crate) fn format_duration(time: Duration, precision: usize, long: bool) -> String {
+    if long {
+        let minutes = time.as_secs() / 60;
+        let seconds = time.saturating_sub(Duration::from_secs(minutes * 60));
+        format!("{minutes}m{:.precision$}s", seconds.as_secs_f64())
+    } else {
+        format!("{:.precision$}", time.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMES: Times = Times {
+        real: Duration::from_millis(62_500),
+        user: Duration::from_millis(1_250),
+        sys: Duration::from_millis(3_750),
+    };
+
+    #[test]
+    fn durations() {
+        let time = Duration::from_millis(62_345);
+        assert_eq!(format_duration(time, 3, false), "62.345");
+        assert_eq!(format_duration(time, 1, false), "62.3");
+        assert_eq!(format_duration(time, 0, false), "62");
+        assert_eq!(format_duration(time, 3, true), "1m2.345s");
+        assert_eq!(format_duration(time, 0, true), "1m2s");
+        assert_eq!(format_duration(Duration::ZERO, 3, true), "0m0.000s");
+    }
+
+    #[test]
+    fn time_format_escapes() {
+        assert_eq!(TIMES.format("%R %U %S"), "62.500 1.250 3.750");
+        assert_eq!(TIMES.format("%lR|%2lU|%0U"), "1m2.500s|0m1.25s|1");
+        // the precision is at most 3 digits
+        assert_eq!(TIMES.format("%9R"), "62.500");
+        assert_eq!(TIMES.format("%P%% %1P"), "8.000% 8.0");
+        assert_eq!(TIMES.format("%x %"), "%x %");
+        assert_eq!(
+            TIMES.format(DEFAULT_TIMEFORMAT),
+            "\nreal\t1m2.500s\nuser\t0m1.250s\nsys\t0m3.750s"
+        );
+        assert_eq!(
+            TIMES.format(POSIX_TIMEFORMAT),
+            "real 62.50\nuser 1.25\nsys 3.75"
+        );
+    }
+}
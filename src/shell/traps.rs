@@ -0,0 +1,187 @@
+use std::fmt;
+
+use nix::sys::signal::Signal;
+
+use crate::{
+    parser::parse_script,
+    shell::{Shell, error::ShellError, signals},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Condition a trap runs on, a signal or one of the pseudo-signals raised by the shell itself
+/// ordered like `trap -p` lists them
+pub(crate) enum Trap {
+    /// `EXIT` (or `0`): the shell exits
+    Exit,
+    /// A signal caught by the shell
+    Signal(Signal),
+    /// `DEBUG`: a simple command is about to be executed
+    Debug,
+    /// `ERR`: a simple command failed
+    Err,
+    /// `RETURN`: a function or a file executed by `source` finished
+    Return,
+}
+
+impl Trap {
+    /// Parse a condition given like a signal to `kill`, or as `EXIT`, `DEBUG`, `ERR` or `RETURN` in any case
+    pub(crate) fn parse(spec: &str) -> Option<Self> {
+        let name = spec.to_ascii_uppercase();
+        match name.strip_prefix("SIG").unwrap_or(&name) {
+            "0" | "EXIT" => Some(Self::Exit),
+            "DEBUG" => Some(Self::Debug),
+            "ERR" => Some(Self::Err),
+            "RETURN" => Some(Self::Return),
+            _ => signals::parse_signal(spec).map(Self::Signal),
+        }
+    }
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exit => write!(f, "EXIT"),
+            Self::Signal(signal) => write!(f, "SIG{}", signals::signal_name(*signal)),
+            Self::Debug => write!(f, "DEBUG"),
+            Self::Err => write!(f, "ERR"),
+            Self::Return => write!(f, "RETURN"),
+        }
+    }
+}
+
+impl Shell {
+    /// Run `action` on `trap`, an empty action makes the shell and the commands it executes ignore a signal
+    pub(crate) fn set_trap(&mut self, trap: Trap, action: String) {
+        if let Trap::Signal(signal) = trap {
+            if action.is_empty() {
+                signals::ignore_signal(signal);
+            } else {
+                signals::catch_signal(signal);
+            }
+        }
+        self.traps.insert(trap, action);
+    }
+
+    /// Remove the trap on `trap`, giving a signal the action it has without one
+    pub(crate) fn reset_trap(&mut self, trap: Trap) {
+        if self.traps.remove(&trap).is_some()
+            && let Trap::Signal(signal) = trap
+        {
+            signals::reset_signal(signal, self.interrupt_guard.is_some());
+        }
+    }
+
+    /// Forget the traps in a forked copy of the shell, only ignored signals stay ignored
+    pub(crate) fn reset_traps(&mut self) {
+        let traps: Vec<_> = self
+            .traps
+            .iter()
+            .filter(|(_, action)| !action.is_empty())
+            .map(|(trap, _)| *trap)
+            .collect();
+        for trap in traps {
+            self.reset_trap(trap);
+        }
+    }
+
+    /// Run the traps of the signals caught since their traps last ran, which happens between commands
+    /// a foreground command killed by Ctrl-C counts as `SIGINT` for a trap on it rather than interrupting the shell
+    pub(crate) fn run_pending_traps(&mut self) {
+        if self.trap_depth > 0 {
+            return;
+        }
+        if signals::interrupted() && self.traps.contains_key(&Trap::Signal(Signal::SIGINT)) {
+            signals::clear_interrupt();
+            signals::set_pending(Signal::SIGINT);
+        }
+        for signal in signals::take_pending() {
+            self.run_trap(Trap::Signal(signal));
+        }
+    }
+
+    /// Run the `DEBUG` or `ERR` trap, which like in bash do not run inside functions nor inside other traps
+    pub(crate) fn run_command_trap(&mut self, trap: Trap) {
+        if self.call_depth == 0 && self.trap_depth == 0 {
+            self.run_trap(trap);
+        }
+    }
+
+    /// Run the `EXIT` trap if it is set and did not run yet, `Shell::exit` does so as well
+    /// the trap may call `exit` to change the status the shell exits with
+    pub fn run_exit_trap(&mut self) {
+        // a script ended by Ctrl-C still runs its cleanup
+        signals::clear_interrupt();
+        self.run_trap(Trap::Exit);
+        self.traps.remove(&Trap::Exit);
+    }
+
+    /// Run the action set for `trap`, keeping `$?` and any pending `return` or `exit` unless the action changes
+    /// the flow itself
+    pub(crate) fn run_trap(&mut self, trap: Trap) {
+        let Some(action) = self.traps.get(&trap).filter(|action| !action.is_empty()) else {
+            return;
+        };
+        let commands = match parse_script(action) {
+            Ok(commands) => commands,
+            Err(e) => {
+                eprintln!("{}{}", self.error_prefix(), ShellError::from(e));
+                return;
+            }
+        };
+        let status = self.last_status;
+        let flow = self.flow.take();
+        self.trap_depth += 1;
+        self.execute(&commands);
+        self.trap_depth -= 1;
+        if self.flow.is_none() {
+            self.flow = flow;
+            self.last_status = status;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_conditions() {
+        for spec in ["EXIT", "exit", "0", "SIGEXIT"] {
+            assert_eq!(Trap::parse(spec), Some(Trap::Exit), "{spec}");
+        }
+        assert_eq!(Trap::parse("debug"), Some(Trap::Debug));
+        assert_eq!(Trap::parse("ERR"), Some(Trap::Err));
+        assert_eq!(Trap::parse("Return"), Some(Trap::Return));
+        for spec in ["INT", "SIGINT", "sigint", "2"] {
+            assert_eq!(
+                Trap::parse(spec),
+                Some(Trap::Signal(Signal::SIGINT)),
+                "{spec}"
+            );
+        }
+        for spec in ["", "NOPE", "-1", "999", "EXIT0"] {
+            assert_eq!(Trap::parse(spec), None, "{spec}");
+        }
+    }
+
+    #[test]
+    fn display_and_order() {
+        let mut traps = [
+            Trap::Return,
+            Trap::Signal(Signal::SIGTERM),
+            Trap::Err,
+            Trap::Debug,
+            Trap::Signal(Signal::SIGHUP),
+            Trap::Exit,
+        ];
+        traps.sort();
+        let names: Vec<_> = traps.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            names,
+            ["EXIT", "SIGHUP", "SIGTERM", "DEBUG", "ERR", "RETURN"]
+        );
+        for trap in traps {
+            assert_eq!(Trap::parse(&trap.to_string()), Some(trap));
+        }
+    }
+}
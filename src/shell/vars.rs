@@ -0,0 +1,593 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    parser::quote,
+    shell::{arith::eval_arith, error::ShellError, special::DynamicVars},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+/// Attributes of a shell variable as set by `declare`, `local`, `readonly` and `export`
+pub(crate) struct Attributes {
+    pub(crate) readonly: bool,
+    pub(crate) exported: bool,
+    /// assignments are evaluated as arithmetic expressions
+    pub(crate) integer: bool,
+    /// assignments are converted to lowercase
+    pub(crate) lowercase: bool,
+    /// assignments are converted to uppercase
+    pub(crate) uppercase: bool,
+}
+
+impl Attributes {
+    /// Whether every attribute set in `other` is also set in `self`
+    pub(crate) fn contains(self, other: Attributes) -> bool {
+        (!other.readonly || self.readonly)
+            && (!other.exported || self.exported)
+            && (!other.integer || self.integer)
+            && (!other.lowercase || self.lowercase)
+            && (!other.uppercase || self.uppercase)
+    }
+
+    /// The flags of the attributes as used by `declare`, in the order bash prints them
+    pub(crate) fn flags(self) -> String {
+        [
+            (self.integer, 'i'),
+            (self.lowercase, 'l'),
+            (self.readonly, 'r'),
+            (self.uppercase, 'u'),
+            (self.exported, 'x'),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The value of a shell variable
+pub(crate) enum Value {
+    Scalar(String),
+    /// An indexed array, which may be sparse
+    Indexed(BTreeMap<usize, String>),
+    /// An associative array declared with `declare -A`
+    Assoc(BTreeMap<String, String>),
+}
+
+impl Value {
+    /// The value used when the variable is referenced without a subscript, the element `0` of arrays
+    pub(crate) fn scalar(&self) -> Option<&str> {
+        match self {
+            Value::Scalar(value) => Some(value),
+            Value::Indexed(elements) => elements.get(&0).map(String::as_str),
+            Value::Assoc(elements) => elements.get("0").map(String::as_str),
+        }
+    }
+
+    /// All values, in order of their index or key
+    pub(crate) fn values(&self) -> Vec<&str> {
+        match self {
+            Value::Scalar(value) => vec![value],
+            Value::Indexed(elements) => elements.values().map(String::as_str).collect(),
+            Value::Assoc(elements) => elements.values().map(String::as_str).collect(),
+        }
+    }
+
+    /// All indices or keys, as expanded by `${!name[@]}`
+    pub(crate) fn keys(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(_) => vec!["0".into()],
+            Value::Indexed(elements) => elements.keys().map(ToString::to_string).collect(),
+            Value::Assoc(elements) => elements.keys().cloned().collect(),
+        }
+    }
+
+    fn into_indexed(self) -> BTreeMap<usize, String> {
+        match self {
+            Value::Scalar(value) => BTreeMap::from([(0, value)]),
+            Value::Indexed(elements) => elements,
+            Value::Assoc(_) => BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An expanded value to be assigned to a variable
+pub(crate) enum AssignedValue {
+    Scalar(String),
+    /// The elements of a compound `(a [key]=b)` assignment with their optional subscripts
+    Array(Vec<(Option<String>, String)>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A shell variable, `value` is `None` for variables that are declared but not set (`local x`)
+pub(crate) struct Variable {
+    pub(crate) value: Option<Value>,
+    pub(crate) attrs: Attributes,
+}
+
+#[derive(Debug, Clone)]
+/// Store of the shell variables with a scope per function call, the first scope holds the global variables
+pub(crate) struct Variables {
+    scopes: Vec<HashMap<String, Variable>>,
+    /// values of `RANDOM`, `SECONDS` and `LINENO`, which are computed rather than stored
+    pub(crate) dynamic: DynamicVars,
+}
+
+impl Default for Variables {
+    /// A store with an empty global scope
+    fn default() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            dynamic: DynamicVars::new(),
+        }
+    }
+}
+
+impl Variables {
+    /// Create the global scope from the environment the shell was started with, marking every variable as exported
+    pub(crate) fn from_env() -> Self {
+        let globals = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .map(|(name, value)| {
+                let var = Variable {
+                    value: Some(Value::Scalar(value)),
+                    attrs: Attributes {
+                        exported: true,
+                        ..Attributes::default()
+                    },
+                };
+                (name, var)
+            })
+            .collect();
+        Self {
+            scopes: vec![globals],
+            dynamic: DynamicVars::new(),
+        }
+    }
+
+    pub(crate) fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Number of scopes, the global scope included
+    pub(crate) fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The value of a set variable, the element `0` for arrays
+    pub(crate) fn value(&self, name: &str) -> Option<&str> {
+        self.get(name)?.value.as_ref()?.scalar()
+    }
+
+    /// The element `key` of the array `name`, where the subscript of indexed arrays is an arithmetic expression
+    /// and negative indices count back from the end
+    ///
+    /// # Errors
+    /// - `ShellError::Arithmetic` if the subscript of an indexed array is not a valid expression
+    /// - `ShellError::BadArraySubscript` if a negative index is out of range
+    pub(crate) fn element(&self, name: &str, key: &str) -> Result<Option<&str>, ShellError> {
+        let Some(value) = self.get(name).and_then(|var| var.value.as_ref()) else {
+            return Ok(None);
+        };
+        if let Value::Assoc(elements) = value {
+            return Ok(elements.get(key).map(String::as_str));
+        }
+        let next_index = match value {
+            Value::Indexed(elements) => elements.last_key_value().map_or(0, |(i, _)| i + 1),
+            _ => 1,
+        };
+        let index = self.index(name, key, next_index)?;
+        Ok(match value {
+            Value::Indexed(elements) => elements.get(&index).map(String::as_str),
+            _ => value.scalar().filter(|_| index == 0),
+        })
+    }
+
+    fn index(&self, name: &str, key: &str, next_index: usize) -> Result<usize, ShellError> {
+        let index = eval_arith(key, self)?;
+        let index = if index < 0 {
+            i64::try_from(next_index).unwrap_or(i64::MAX) + index
+        } else {
+            index
+        };
+        usize::try_from(index).map_err(|_| ShellError::BadArraySubscript(format!("{name}[{key}]")))
+    }
+
+    /// Assign `value` to the visible variable `name`, creating a global variable if it does not exist yet
+    ///
+    /// # Errors
+    /// - `ShellError::ReadonlyVariable` if the variable is readonly
+    /// - `ShellError::Arithmetic` if the variable is an integer and `value` is not a valid expression
+    pub(crate) fn set(&mut self, name: &str, value: &str) -> Result<(), ShellError> {
+        self.assign(name, None, AssignedValue::Scalar(value.to_string()), false)
+    }
+
+    /// Assign to the visible variable `name` or, with `index`, to one of its elements,
+    /// appending to the current value for `+=` assignments
+    ///
+    /// # Errors
+    /// - `ShellError::ReadonlyVariable` if the variable is readonly
+    /// - `ShellError::Arithmetic` if a subscript or the value of an integer variable is not a valid expression
+    /// - `ShellError::BadArraySubscript` if a negative index is out of range
+    /// - `ShellError::AssocSubscriptRequired` if an element assigned to an associative array has no key
+    pub(crate) fn assign(
+        &mut self,
+        name: &str,
+        index: Option<&str>,
+        value: AssignedValue,
+        append: bool,
+    ) -> Result<(), ShellError> {
+        let (attrs, current) = match self.get(name) {
+            Some(var) => (var.attrs, var.value.clone()),
+            None => (Attributes::default(), None),
+        };
+        if attrs.readonly {
+            return Err(ShellError::ReadonlyVariable(name.to_string()));
+        }
+
+        let new_value = match (value, current) {
+            (AssignedValue::Scalar(value), Some(Value::Assoc(mut elements))) => {
+                let key = index.unwrap_or("0");
+                let value = self.apply_attrs(attrs, elements.get(key), &value, append)?;
+                elements.insert(key.to_string(), value);
+                Value::Assoc(elements)
+            }
+            // assigning to an element turns a scalar into an indexed array
+            (AssignedValue::Scalar(value), current)
+                if index.is_some() || matches!(current, Some(Value::Indexed(_))) =>
+            {
+                let mut elements = current.map(Value::into_indexed).unwrap_or_default();
+                let next_index = elements.last_key_value().map_or(0, |(i, _)| i + 1);
+                let index = self.index(name, index.unwrap_or("0"), next_index)?;
+                let value = self.apply_attrs(attrs, elements.get(&index), &value, append)?;
+                elements.insert(index, value);
+                Value::Indexed(elements)
+            }
+            (AssignedValue::Scalar(value), current) => {
+                let current = current
+                    .as_ref()
+                    .and_then(Value::scalar)
+                    .map(ToString::to_string);
+                Value::Scalar(self.apply_attrs(attrs, current.as_ref(), &value, append)?)
+            }
+            (AssignedValue::Array(new_elements), Some(Value::Assoc(elements))) => {
+                let mut elements = if append { elements } else { BTreeMap::new() };
+                for (key, value) in new_elements {
+                    let Some(key) = key else {
+                        return Err(ShellError::AssocSubscriptRequired(name.to_string()));
+                    };
+                    let value = self.apply_attrs(attrs, elements.get(&key), &value, append)?;
+                    elements.insert(key, value);
+                }
+                Value::Assoc(elements)
+            }
+            (AssignedValue::Array(new_elements), current) => {
+                let mut elements = match current {
+                    Some(current) if append => current.into_indexed(),
+                    _ => BTreeMap::new(),
+                };
+                let mut next_index = elements.last_key_value().map_or(0, |(i, _)| i + 1);
+                for (key, value) in new_elements {
+                    let index = match key {
+                        Some(key) => self.index(name, &key, next_index)?,
+                        None => next_index,
+                    };
+                    let value = self.apply_attrs(attrs, None, &value, false)?;
+                    elements.insert(index, value);
+                    next_index = index + 1;
+                }
+                Value::Indexed(elements)
+            }
+        };
+        self.declare(name, false).value = Some(new_value);
+        Ok(())
+    }
+
+    fn apply_attrs(
+        &self,
+        attrs: Attributes,
+        current: Option<&String>,
+        value: &str,
+        append: bool,
+    ) -> Result<String, ShellError> {
+        let current = current.filter(|_| append).map_or("", String::as_str);
+        Ok(if attrs.integer {
+            // appending to an integer variable adds to it
+            let value = eval_arith(value, self)?;
+            eval_arith(current, self)?.wrapping_add(value).to_string()
+        } else {
+            let value = format!("{current}{value}");
+            if attrs.lowercase {
+                value.to_lowercase()
+            } else if attrs.uppercase {
+                value.to_uppercase()
+            } else {
+                value
+            }
+        })
+    }
+
+    /// Get the variable `name` for changing its attributes, creating it in the innermost scope when `local`
+    /// and in the scope it is visible in (or the global scope) otherwise
+    pub(crate) fn declare(&mut self, name: &str, local: bool) -> &mut Variable {
+        let scope_idx = if local {
+            self.scopes.len() - 1
+        } else {
+            self.scopes
+                .iter()
+                .rposition(|scope| scope.contains_key(name))
+                .unwrap_or(0)
+        };
+        self.scopes[scope_idx].entry(name.to_string()).or_default()
+    }
+
+    /// Remove the visible variable `name`
+    ///
+    /// # Errors
+    /// - `ShellError::CannotUnsetReadonly` if the variable is readonly
+    pub(crate) fn unset(&mut self, name: &str) -> Result<(), ShellError> {
+        if self.get(name).is_some_and(|var| var.attrs.readonly) {
+            return Err(ShellError::CannotUnsetReadonly(name.to_string()));
+        }
+        if let Some(scope) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_key(name))
+        {
+            scope.remove(name);
+        }
+        Ok(())
+    }
+
+    /// Remove the element `key` of the visible array `name`
+    ///
+    /// # Errors
+    /// - `ShellError::CannotUnsetReadonly` if the variable is readonly
+    /// - `ShellError::Arithmetic` if the subscript of an indexed array is not a valid expression
+    /// - `ShellError::BadArraySubscript` if a negative index is out of range
+    pub(crate) fn unset_element(&mut self, name: &str, key: &str) -> Result<(), ShellError> {
+        let Some(var) = self.get(name) else {
+            return Ok(());
+        };
+        if var.attrs.readonly {
+            return Err(ShellError::CannotUnsetReadonly(name.to_string()));
+        }
+        let index = match &var.value {
+            Some(Value::Indexed(elements)) => {
+                let next_index = elements.last_key_value().map_or(0, |(i, _)| i + 1);
+                Some(self.index(name, key, next_index)?)
+            }
+            _ => None,
+        };
+        let var = self.declare(name, false);
+        match (&mut var.value, index) {
+            (Some(Value::Indexed(elements)), Some(index)) => {
+                elements.remove(&index);
+            }
+            (Some(Value::Assoc(elements)), _) => {
+                elements.remove(key);
+            }
+            (Some(Value::Scalar(_)), _) if key == "0" => var.value = None,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The name and value of every visible exported variable, which make up the environment of child processes
+    /// arrays cannot be exported and are left out
+    pub(crate) fn exported(&self) -> Vec<(&str, &str)> {
+        self.visible()
+            .into_iter()
+            .filter(|(_, var)| var.attrs.exported)
+            .filter_map(|(name, var)| match &var.value {
+                Some(Value::Scalar(value)) => Some((name, value.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All visible variables, sorted by name
+    pub(crate) fn visible(&self) -> BTreeMap<&str, &Variable> {
+        let mut visible = BTreeMap::new();
+        for scope in &self.scopes {
+            for (name, var) in scope {
+                visible.insert(name.as_str(), var);
+            }
+        }
+        visible
+    }
+}
+
+/// Whether `name` can be used as a variable name
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Format a variable the way `declare -p` prints it, so the output can be read back by the shell
+pub(crate) fn declaration(name: &str, var: &Variable) -> String {
+    let kind = match var.value {
+        Some(Value::Indexed(_)) => "a",
+        Some(Value::Assoc(_)) => "A",
+        _ => "",
+    };
+    let flags = format!("{kind}{}", var.attrs.flags());
+    let flags = if flags.is_empty() { "-".into() } else { flags };
+    match &var.value {
+        Some(value) => format!("declare -{flags} {name}={}", format_value(value)),
+        None => format!("declare -{flags} {name}"),
+    }
+}
+
+/// Format a value as the right hand side of an assignment, arrays in their compound `([key]="value")` form
+pub(crate) fn format_value(value: &Value) -> String {
+    let elements: Vec<_> = match value {
+        Value::Scalar(value) => return double_quote(value),
+        Value::Indexed(elements) => elements
+            .iter()
+            .map(|(index, value)| format!("[{index}]={}", double_quote(value)))
+            .collect(),
+        Value::Assoc(elements) => elements
+            .iter()
+            .map(|(key, value)| format!("[{}]={}", quote(key), double_quote(value)))
+            .collect(),
+    };
+    format!("({})", elements.join(" "))
+}
+
+fn double_quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        if matches!(c, '\"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(value: &str) -> AssignedValue {
+        AssignedValue::Scalar(value.into())
+    }
+
+    #[test]
+    fn scopes_shadow_and_restore() {
+        let mut vars = Variables::default();
+        assert!(vars.set("x", "global").is_ok());
+        vars.push_scope();
+        vars.declare("x", true).value = Some(Value::Scalar("local".into()));
+        assert_eq!(vars.value("x"), Some("local"));
+        // assignments go to the visible variable, new variables are global
+        assert!(vars.set("x", "changed").is_ok());
+        assert!(vars.set("y", "new").is_ok());
+        vars.pop_scope();
+        assert_eq!(vars.value("x"), Some("global"));
+        assert_eq!(vars.value("y"), Some("new"));
+        // the global scope is never popped
+        vars.pop_scope();
+        assert_eq!(vars.value("x"), Some("global"));
+    }
+
+    #[test]
+    fn unset_reveals_shadowed_variable() {
+        let mut vars = Variables::default();
+        assert!(vars.set("x", "global").is_ok());
+        vars.push_scope();
+        vars.declare("x", true);
+        assert_eq!(vars.value("x"), None);
+        assert!(vars.unset("x").is_ok());
+        assert_eq!(vars.value("x"), Some("global"));
+    }
+
+    #[test]
+    fn readonly_variables() {
+        let mut vars = Variables::default();
+        assert!(vars.set("x", "1").is_ok());
+        vars.declare("x", false).attrs.readonly = true;
+        assert!(matches!(
+            vars.set("x", "2"),
+            Err(ShellError::ReadonlyVariable(name)) if name == "x"
+        ));
+        assert!(matches!(
+            vars.unset("x"),
+            Err(ShellError::CannotUnsetReadonly(name)) if name == "x"
+        ));
+        assert_eq!(vars.value("x"), Some("1"));
+    }
+
+    #[test]
+    fn exported_variables() {
+        let mut vars = Variables::default();
+        assert!(vars.set("a", "1").is_ok());
+        assert!(vars.set("b", "2").is_ok());
+        assert!(vars.assign("arr", Some("0"), scalar("x"), false).is_ok());
+        for name in ["b", "arr"] {
+            vars.declare(name, false).attrs.exported = true;
+        }
+        vars.push_scope();
+        let local = vars.declare("b", true);
+        local.value = Some(Value::Scalar("local".into()));
+        local.attrs.exported = true;
+        // arrays are never exported, local variables shadow global ones
+        assert_eq!(vars.exported(), vec![("b", "local")]);
+    }
+
+    #[test]
+    fn attributes_apply_to_assignments() {
+        let mut vars = Variables::default();
+        vars.declare("n", false).attrs.integer = true;
+        assert!(vars.set("n", "2 * 3").is_ok());
+        assert_eq!(vars.value("n"), Some("6"));
+        // `+=` adds to integer variables and appends to others
+        assert!(vars.assign("n", None, scalar("4"), true).is_ok());
+        assert_eq!(vars.value("n"), Some("10"));
+        assert!(vars.set("s", "ab").is_ok());
+        vars.declare("s", false).attrs.uppercase = true;
+        assert!(vars.assign("s", None, scalar("cd"), true).is_ok());
+        assert_eq!(vars.value("s"), Some("ABCD"));
+        assert!(matches!(
+            vars.set("n", "1 /"),
+            Err(ShellError::Arithmetic(..))
+        ));
+        assert_eq!(vars.value("n"), Some("10"));
+    }
+
+    #[test]
+    fn declaration_quotes_values() {
+        let var = |value, attrs| Variable {
+            value: Some(value),
+            attrs,
+        };
+        let tricky = r#"a "b" $c \d `e`"#;
+        assert_eq!(
+            declaration(
+                "x",
+                &var(Value::Scalar(tricky.into()), Attributes::default())
+            ),
+            r#"declare -- x="a \"b\" \$c \\d \`e\`""#
+        );
+        let exported = Attributes {
+            exported: true,
+            integer: true,
+            ..Attributes::default()
+        };
+        assert_eq!(
+            declaration("n", &var(Value::Scalar("1".into()), exported)),
+            r#"declare -ix n="1""#
+        );
+        let indexed = Value::Indexed(BTreeMap::from([(0, "a b".into()), (3, "$x".into())]));
+        assert_eq!(
+            declaration("arr", &var(indexed, Attributes::default())),
+            r#"declare -a arr=([0]="a b" [3]="\$x")"#
+        );
+        let assoc = Value::Assoc(BTreeMap::from([("two words".into(), "v".into())]));
+        assert_eq!(
+            declaration("map", &var(assoc, Attributes::default())),
+            r#"declare -A map=(['two words']="v")"#
+        );
+        assert_eq!(
+            declaration("unset", &Variable::default()),
+            "declare -- unset"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! Aliases expand in command position of every command of a pipeline
+
+mod common;
+
+use common::run;
+
+#[test]
+fn aliases_expand_after_pipes() {
+    let script = r"
+        alias greet='echo hello' upper='tr a-z A-Z' ls='ls -d'
+        greet | upper
+        echo world | upper | cat
+        ls / | upper
+        echo greet | cat
+        ";
+    assert_eq!(run(script), "HELLO\nWORLD\n/\ngreet\n");
+}
+
+#[test]
+fn chained_aliases_expand() {
+    let script = r"
+        alias first=second second='tr a-z A-Z'
+        echo x | first
+        echo y | first | cat
+        ";
+    assert_eq!(run(script), "X\nY\n");
+}
@@ -0,0 +1,40 @@
+//! Assignments preceding a command apply to that command only, on their own they set shell variables
+
+mod common;
+
+use common::run;
+
+#[test]
+fn assignment_applies_to_its_command() {
+    assert_eq!(
+        run(r#"FOO=1 sh -c 'echo "[$FOO]"'; echo "[$FOO]""#),
+        "[1]\n[]\n"
+    );
+}
+
+#[test]
+fn assignment_does_not_reach_rest_of_pipeline() {
+    let script = r#"
+        FOO=1 sh -c 'echo "first [$FOO]"' | sh -c 'cat; echo "second [$FOO]"'
+        FOO=2 echo builtin | sh -c 'cat; echo "after builtin [$FOO]"'
+        f() { echo "function [$FOO]"; }
+        FOO=3 f | sh -c 'cat; echo "after function [$FOO]"'
+    "#;
+    assert_eq!(
+        run(script),
+        "first [1]\nsecond []\nbuiltin\nafter builtin []\nfunction [3]\nafter function []\n"
+    );
+}
+
+#[test]
+fn assignment_status() {
+    let script = r#"
+        false; status=$?; echo "previous $status $?"
+        false; x=$(exit 3); echo "substitution $?"
+        false; x=$(exit 3) y=$(true); echo "last substitution $?"
+    "#;
+    assert_eq!(
+        run(script),
+        "previous 1 0\nsubstitution 3\nlast substitution 0\n"
+    );
+}
@@ -0,0 +1,41 @@
+//! Background commands and the pid `$!` expands to
+
+mod common;
+
+use common::run;
+
+#[test]
+fn simple_command_is_the_background_process() {
+    let script = r#"
+        sleep 77 &
+        ps -o comm= -p $!
+        kill $!
+        wait $!
+        echo "status $?"
+        ps -o comm= -p $!
+        echo "ps $?"
+        "#;
+    assert_eq!(run(script), "sleep\nstatus 143\nps 1\n");
+}
+
+#[test]
+fn other_commands_run_in_a_subshell() {
+    let script = r#"
+        f() { sh -c 'exit 3'; echo "after $?"; }
+        f &
+        wait $!
+        echo "function $?"
+        echo one | tr a-z A-Z &
+        wait $!
+        X=2 sh -c 'echo "X=$X"; exit 4' &
+        wait $!
+        echo "external $?"
+        no_such_command &
+        wait $!
+        echo "not found $?"
+        "#;
+    assert_eq!(
+        run(script),
+        "after 3\nfunction 0\nONE\nX=2\nexternal 4\nno_such_command: command not found\nnot found 127\n"
+    );
+}
@@ -0,0 +1,29 @@
+//! The command line decides what the shell reads and its positional parameters
+
+mod common;
+
+use common::{clawsh, stdout};
+
+#[test]
+fn command_string_takes_name_and_arguments() {
+    let output = clawsh(&["-c", r#"echo "$0|$#|$*|$-""#, "name", "a b", "c"], "");
+    assert_eq!(stdout(&output), "name|2|a b c|c\n");
+    let output = clawsh(&["-ec", "echo $1", "name"], "");
+    assert_eq!(stdout(&output), "\n");
+    assert_eq!(clawsh(&["-c"], "").status.code(), Some(2));
+}
+
+#[test]
+fn stdin_takes_all_operands_as_arguments() {
+    let output = clawsh(&["-s", "a", "b"], "echo \"$#|$*|$-\"\n");
+    assert_eq!(stdout(&output), "2|a b|s\n");
+}
+
+#[test]
+fn unknown_option_exits_with_usage() {
+    let output = clawsh(&["-q"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with("clawsh: -q: invalid option\nUsage:")
+    );
+}
@@ -0,0 +1,48 @@
+//! Command substitution runs in a subshell whose state does not leak into the shell
+mod common;
+
+use common::run;
+
+#[test]
+fn output_is_substituted() {
+    assert_eq!(run(r#"x=$(printf 'a\n\n'); echo "[$x]""#), "[a]\n");
+    assert_eq!(run("set -- $(echo a b   c); echo $#"), "3\n");
+    assert_eq!(run(r#"set -- "$(echo a b)"; echo $#"#), "1\n");
+}
+
+#[test]
+fn status_is_the_subshells() {
+    assert_eq!(run("x=$(exit 3); echo $?"), "3\n");
+    assert_eq!(run("x=$(false) y=$(true); echo $?"), "0\n");
+}
+
+#[test]
+fn subshell_state_does_not_leak() {
+    let script = r#"
+        cd /
+        x=$(cd /tmp; v=changed; f() { :; }; alias a=b; set -e; set -- p; trap 'echo trap' EXIT; echo $$)
+        echo $x
+        echo $$
+        pwd
+        echo "v=$v args=$# opts=$-"
+        type f
+        alias
+        trap -p
+    "#;
+    let output = run(script);
+    let lines: Vec<_> = output.lines().collect();
+    let [substituted, pid, rest @ ..] = lines.as_slice() else {
+        panic!("unexpected output {output:?}");
+    };
+    // `$$` is the parent's pid in the subshell, whose `EXIT` trap runs when it exits
+    assert_eq!(*substituted, format!("{pid} trap"));
+    assert_eq!(rest, ["/", "v= args=0 opts=c", "f: not found"]);
+}
+
+#[test]
+fn jobs_stay_with_the_parent() {
+    assert_eq!(
+        run("sleep 1 & n=$(jobs | wc -l); echo $n; jobs | wc -l"),
+        "0\n1\n"
+    );
+}
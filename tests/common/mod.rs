@@ -0,0 +1,46 @@
+//! Helpers for running the `clawsh` binary in the integration tests
+// every test crate uses a different part of the helpers, and a failure to run the shell fails the test
+#![allow(dead_code, clippy::expect_used)]
+
+pub mod terminal;
+
+use std::{
+    io::{ErrorKind, Write},
+    process::{Command, Output, Stdio},
+};
+
+/// Run `clawsh` with `args` and `stdin`, outside of any startup files
+pub fn clawsh(args: &[&str], stdin: &str) -> Output {
+    clawsh_at(env!("CARGO_TARGET_TMPDIR"), args, stdin)
+}
+
+/// Run `clawsh` with `args` and `stdin` and `home` as its home directory
+pub fn clawsh_at(home: &str, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_clawsh"))
+        .args(args)
+        .env("HOME", home)
+        .env_remove("HISTFILE")
+        .env_remove("XDG_CONFIG_HOME")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("clawsh should start");
+    if let Some(mut input) = child.stdin.take()
+        && let Err(e) = input.write_all(stdin.as_bytes())
+    {
+        // the shell may exit without reading its input
+        assert_eq!(e.kind(), ErrorKind::BrokenPipe, "stdin should be writable");
+    }
+    child.wait_with_output().expect("clawsh should finish")
+}
+
+/// Run a command string with `clawsh -c` and return its stdout
+pub fn run(script: &str) -> String {
+    stdout(&clawsh(&["-c", script], ""))
+}
+
+/// The stdout of a finished `clawsh`
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
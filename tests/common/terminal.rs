@@ -0,0 +1,137 @@
+//! An interactive `clawsh` on a pseudo-terminal of its own, so it runs with job control
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    os::{fd::AsRawFd, unix::process::CommandExt},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use nix::pty::openpty;
+
+/// How long to wait for output before failing the test
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A `clawsh` controlled by writing to its terminal and reading what it prints there
+pub struct Terminal {
+    master: File,
+    child: Child,
+    output: String,
+}
+
+impl Terminal {
+    /// Start an interactive `clawsh --norc` with `args` on a new terminal, with `$ ` as its prompt
+    /// the dumb terminal keeps the line editor from redrawing the line, so the terminal only echoes the input
+    pub fn start(args: &[&str]) -> Self {
+        let pty = openpty(None, None).expect("a pseudo-terminal should open");
+        let stdio = || Stdio::from(pty.slave.try_clone().expect("the terminal should dup"));
+        let mut command = Command::new(env!("CARGO_BIN_EXE_clawsh"));
+        command
+            .arg("--norc")
+            .args(args)
+            .env("HOME", env!("CARGO_TARGET_TMPDIR"))
+            .env("PS1", "$ ")
+            .env("TERM", "dumb")
+            .env_remove("HISTFILE")
+            .stdin(stdio())
+            .stdout(stdio())
+            .stderr(stdio());
+        // SAFETY: `setsid` and `ioctl` are async-signal-safe
+        #[allow(unsafe_code)]
+        unsafe {
+            command.pre_exec(|| {
+                // a session of its own with the terminal as its controlling terminal
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn().expect("clawsh should start");
+        let mut terminal = Self {
+            master: File::from(pty.master),
+            child,
+            output: String::new(),
+        };
+        terminal.expect("$ ");
+        terminal
+    }
+
+    /// Type `input`, e.g. a command line ending in `\r` or a control character like `\x03` for Ctrl-C
+    pub fn send(&mut self, input: &str) {
+        self.master
+            .write_all(input.as_bytes())
+            .expect("the terminal should be writable");
+    }
+
+    /// Run the command `line` at the prompt, returning what it printed before the next prompt
+    pub fn run(&mut self, line: &str) -> String {
+        self.send(&format!("{line}\r"));
+        // the terminal echoes the line as it is typed
+        self.expect(&format!("{line}\r\n"));
+        self.expect("$ ").replace("\r\n", "\n")
+    }
+
+    /// Wait until the shell printed `text`, returning everything printed before it
+    pub fn expect(&mut self, text: &str) -> String {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(index) = self.output.find(text) {
+                let rest = self.output.split_off(index + text.len());
+                let mut before = std::mem::replace(&mut self.output, rest);
+                before.truncate(index);
+                return before;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for {text:?}, got {:?}",
+                self.output
+            );
+            self.read();
+        }
+    }
+
+    /// Wait for the shell to exit, returning its exit status
+    pub fn wait(&mut self) -> Option<i32> {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().expect("clawsh should be waitable") {
+                return status.code();
+            }
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for clawsh to exit"
+            );
+            self.read();
+        }
+    }
+
+    fn read(&mut self) {
+        let mut fds = [libc::pollfd {
+            fd: self.master.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        // SAFETY: `fds` is a valid array of one `pollfd` for the whole call
+        #[allow(unsafe_code)]
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, 100) };
+        if ready <= 0 {
+            return;
+        }
+        let mut buf = [0; 4096];
+        match self.master.read(&mut buf) {
+            Ok(read) if read > 0 => self.output.push_str(&String::from_utf8_lossy(&buf[..read])),
+            // the shell closed the terminal
+            _ => thread::sleep(Duration::from_millis(100)),
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,32 @@
+//! `declare -p` prints variables in a form the shell reads back to the same values
+
+mod common;
+
+use common::run;
+
+#[test]
+fn declarations_read_back() {
+    let file = format!("{}/declarations", env!("CARGO_TARGET_TMPDIR"));
+    let script = format!(
+        r#"
+        s='a "b" $c \d `e` '\''f'\'''
+        declare -ix n=42
+        declare -a arr
+        arr[0]='x y'
+        arr[3]="$s"
+        declare -A map
+        map["two words"]="$s"
+        map["it's"]='$HOME'
+        declare -p s n arr map > {file}
+        declare -p s n arr map
+        unset s n arr map
+        source {file}
+        declare -p s n arr map
+        "#
+    );
+    let output = run(&script);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 8, "{output}");
+    assert_eq!(lines[..4], lines[4..]);
+    assert_eq!(lines[0], r#"declare -- s="a \"b\" \$c \\d \`e\` 'f'""#);
+}
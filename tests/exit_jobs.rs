@@ -0,0 +1,65 @@
+//! An interactive shell warns once before exiting with stopped jobs, which get `SIGHUP` when it exits anyway
+
+mod common;
+
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+use common::terminal::Terminal;
+
+/// Start `command` and stop it with Ctrl-Z
+fn stop(terminal: &mut Terminal, command: &str) {
+    terminal.send(&format!("{command}\r"));
+    terminal.expect("started\r\n");
+    terminal.send("\x1a");
+    terminal.expect("Stopped");
+    terminal.expect("$ ");
+}
+
+#[test]
+fn exit_warns_about_stopped_jobs() {
+    let mut terminal = Terminal::start(&[]);
+    // running jobs do not hold off exiting, only stopped ones do
+    terminal.run("sleep 30 &");
+    stop(&mut terminal, "sh -c 'echo started; exec sleep 30'");
+    assert_eq!(terminal.run("exit"), "There are stopped jobs.\n");
+    // another command in between makes the next `exit` warn again
+    assert_eq!(terminal.run("jobs -s").lines().count(), 1);
+    assert_eq!(terminal.run("exit"), "There are stopped jobs.\n");
+    terminal.send("exit\r");
+    assert_eq!(terminal.wait(), Some(0));
+}
+
+#[test]
+fn end_of_input_warns_about_stopped_jobs() {
+    let mut terminal = Terminal::start(&[]);
+    stop(&mut terminal, "sh -c 'echo started; exec sleep 30'");
+    terminal.send("\x04");
+    terminal.expect("There are stopped jobs.\r\n");
+    terminal.expect("$ ");
+    terminal.send("\x04");
+    // with the status of the last command, the one that stopped
+    assert_eq!(terminal.wait(), Some(148));
+}
+
+#[test]
+fn stopped_jobs_are_hung_up_on_exit() {
+    let file = format!("{}/hung_up", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_file(&file);
+    let mut terminal = Terminal::start(&[]);
+    stop(
+        &mut terminal,
+        &format!("sh -c 'trap \"echo hangup > {file}; exit\" HUP; sleep 30 & echo started; wait'"),
+    );
+    assert_eq!(terminal.run("exit"), "There are stopped jobs.\n");
+    terminal.send("exit\r");
+    assert_eq!(terminal.wait(), Some(148));
+    // the job is continued so it acts on the signal
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while fs::read_to_string(&file).ok().as_deref() != Some("hangup\n") {
+        assert!(Instant::now() < deadline, "the stopped job was not hung up");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,37 @@
+//! `precmd`, `PROMPT_COMMAND` and `preexec` run around each command line of an interactive shell
+
+mod common;
+
+use common::{clawsh, stdout};
+
+#[test]
+fn hooks_run_in_order() {
+    let input = r#"precmd() { echo "precmd $1"; }; preexec() { echo "preexec $1"; }; PROMPT_COMMAND=('echo "first $?"; false' 'echo "second $?"')
+echo command | cat
+false
+"#;
+    let output = clawsh(&["-i", "--norc"], input);
+    assert_eq!(
+        stdout(&output),
+        "precmd 0\nfirst 0\nsecond 0\n\
+         preexec echo command | cat\ncommand\nprecmd 0\nfirst 0\nsecond 0\n\
+         preexec false\nprecmd 1\nfirst 1\nsecond 1\nCTRL-D\n"
+    );
+    // the hooks do not change the status the shell exits with
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn exit_in_a_hook_ends_the_shell() {
+    let input = "PROMPT_COMMAND=('echo first; exit 3' 'echo second')\necho never\n";
+    let output = clawsh(&["-i", "--norc"], input);
+    assert_eq!(stdout(&output), "first\n");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn scalar_prompt_command() {
+    let input = "PROMPT_COMMAND='echo \"prompt $?\"'\nfalse\n";
+    let output = clawsh(&["-i", "--norc"], input);
+    assert_eq!(stdout(&output), "prompt 0\nprompt 1\nCTRL-D\n");
+}
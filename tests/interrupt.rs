@@ -0,0 +1,41 @@
+//! Ctrl-C interrupts the line being edited or the foreground command, never the interactive shell
+
+mod common;
+
+use common::terminal::Terminal;
+
+#[test]
+fn ctrl_c_discards_the_line() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.send("echo discarded");
+    terminal.send("\x03");
+    terminal.expect("^C");
+    assert_eq!(terminal.run("echo \"status $?\""), "status 130\n");
+    assert_eq!(terminal.run("echo \"status $?\""), "status 0\n");
+}
+
+#[test]
+fn ctrl_c_interrupts_the_foreground_command() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.send("sh -c 'echo started; exec sleep 30'; echo not reached\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x03");
+    let output = terminal.expect("$ ");
+    // the rest of the command line is abandoned
+    assert!(!output.contains("not reached"), "{output:?}");
+    assert_eq!(terminal.run("echo \"status $?\""), "status 130\n");
+}
+
+#[test]
+fn ctrl_c_leaves_background_jobs_alone() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.run("sleep 30 &");
+    terminal.send("sh -c 'echo started; exec sleep 30'\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x03");
+    terminal.expect("$ ");
+    assert_eq!(
+        terminal.run("jobs"),
+        "[1]+  Running                 sleep 30 &\n"
+    );
+}
@@ -0,0 +1,34 @@
+//! `kill` sends signals by number or name to processes and jobs
+
+mod common;
+
+use common::{clawsh, run, stdout};
+
+#[test]
+fn signals_by_name_and_number() {
+    let script = r#"
+        sleep 30 & kill -s USR1 %1; wait %1; echo "name $?"
+        sleep 30 & kill -9 $!; wait $!; echo "number $?"
+        sleep 30 & kill -sigterm $!; wait $!; echo "option $?"
+        sh -c 'kill -INT $$; echo ignored' & wait $!
+        sleep 30 & kill $!; wait $!; kill -l $? 10
+        "#;
+    assert_eq!(
+        run(script),
+        "name 138\nnumber 137\noption 143\nignored\nTERM\nUSR1\n"
+    );
+}
+
+#[test]
+fn invalid_signals() {
+    let output = clawsh(
+        &["-c", "kill -s NOPE $$; echo \"$?\"; kill -l 0; echo \"$?\""],
+        "",
+    );
+    assert_eq!(stdout(&output), "1\n1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "command error: kill: NOPE: invalid signal specification\n\
+         command error: kill: 0: invalid signal specification\n"
+    );
+}
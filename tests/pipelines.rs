@@ -0,0 +1,76 @@
+//! Every stage of a pipeline behaves as if it ran in a subshell of its own
+
+mod common;
+
+use common::{clawsh, run, terminal::Terminal};
+
+#[test]
+fn exit_only_ends_its_stage() {
+    let script = r"
+        echo hi | exit; echo after $?
+        true | exit 4; echo status $?
+        exit 5 | cat; echo first $?
+        yes | exit 6; echo reads nothing $?
+        f() { echo hi | exit 3; echo in f $?; }; f
+    ";
+    assert_eq!(
+        run(script),
+        "after 0\nstatus 4\nfirst 0\nreads nothing 6\nin f 3\n"
+    );
+    // the same holds for the lines an interactive shell reads
+    let output = clawsh(&["-i"], "echo hi | exit 3\necho after $?\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("after 3\n"));
+}
+
+#[test]
+fn function_output_streams_to_the_next_stage() {
+    let script = r#"
+        f() { yes; }
+        f | head -1
+        g() { x=$1; cat; }
+        echo piped | g first | tr a-z A-Z
+        echo "[$x]"
+        h() { x=$1; echo hidden; }
+        h second > /dev/null
+        echo "[$x]"
+    "#;
+    // a function in a pipeline changes nothing in the shell, with only its output redirected it does
+    assert_eq!(run(script), "y\nPIPED\n[]\n[second]\n");
+}
+
+#[test]
+fn function_stages_belong_to_the_job() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.run("f() { sh -c 'echo started; exec sleep 30'; }");
+    terminal.send("f | cat\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x03");
+    terminal.expect("$ ");
+    assert_eq!(terminal.run("echo \"status $?\""), "status 130\n");
+
+    terminal.send("f | cat\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x1a");
+    let stopped = terminal.expect("$ ");
+    assert!(
+        stopped.contains("[1]+  Stopped                 f | cat"),
+        "{stopped:?}"
+    );
+    assert_eq!(terminal.run("echo \"status $?\""), "status 148\n");
+}
+
+#[test]
+fn builtins_that_change_state_ignore_piped_input() {
+    let script = r"
+        echo x | declare y=1; echo declare $?
+        set -- a b; echo a | shift; echo shift $?
+        echo z | unset y; echo unset $?
+    ";
+    let output = clawsh(&["-c", script], "");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "declare 0\nshift 0\nunset 0\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
@@ -0,0 +1,51 @@
+//! An interactive shell runs each pipeline in a process group of its own, which gets the terminal
+
+mod common;
+
+use common::terminal::Terminal;
+
+/// The numbers printed on each line
+fn ids(output: &str) -> Vec<Vec<u32>> {
+    output
+        .lines()
+        .map(|line| {
+            line.split_whitespace()
+                .filter_map(|id| id.parse().ok())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn pipeline_gets_a_group_and_the_terminal() {
+    let mut terminal = Terminal::start(&[]);
+    // the shell leads a group of its own
+    let shell = ids(&terminal.run("ps -o pid=,pgid= -p $$"));
+    let [shell] = &shell[..] else {
+        panic!("expected the ids of the shell, got {shell:?}");
+    };
+    assert_eq!(shell[0], shell[1]);
+
+    // the first process of a pipeline leads a new group which gets the terminal, the others join the group
+    // the shell takes the terminal back to read the next command
+    for _ in 0..2 {
+        let pipeline = ids(&terminal.run(
+            "sh -c 'ps -o pid=,pgid=,tpgid= -p $$' | sh -c 'cat; ps -o pid=,pgid=,tpgid= -p $$'",
+        ));
+        let [first, second] = &pipeline[..] else {
+            panic!("expected the ids of two processes, got {pipeline:?}");
+        };
+        assert_eq!(first[..], [first[0], first[0], first[0]]);
+        assert_eq!(second[1..], first[1..]);
+        assert_ne!(second[0], first[0]);
+        assert_ne!(first[1], shell[1]);
+    }
+}
+
+#[test]
+fn scripts_run_commands_in_the_shells_group() {
+    let output = common::run("ps -o pgid= -p $$; sh -c 'ps -o pgid= -p $$' | cat");
+    let groups = ids(&output);
+    assert_eq!(groups.len(), 2, "{output}");
+    assert_eq!(groups[0], groups[1]);
+}
@@ -0,0 +1,51 @@
+//! Scripts execute one complete command at a time, like files run by `source`
+
+mod common;
+
+use std::fs;
+
+use common::{clawsh, stdout};
+
+fn script(name: &str, src: &str) -> String {
+    let file = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    assert!(fs::write(&file, src).is_ok());
+    file
+}
+
+#[test]
+fn commands_before_a_syntax_error_run() {
+    let file = script(
+        "script_syntax_error.sh",
+        "#!/usr/bin/env clawsh\necho one\nfor x in a b\ndo echo $x; done\necho two\ndone\necho never\n",
+    );
+    let output = clawsh(&[&file], "");
+    assert_eq!(stdout(&output), "one\na\nb\ntwo\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{file}: line 6: syntax error near unexpected token `done'\n")
+    );
+}
+
+#[test]
+fn unterminated_command_is_reported_at_the_end() {
+    let file = script("script_incomplete.sh", "echo one\necho 'two\n\n");
+    let output = clawsh(&[&file], "");
+    assert_eq!(stdout(&output), "one\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{file}: line 3: syntax error: unexpected end of file\n")
+    );
+}
+
+#[test]
+fn bad_substitution_fails_its_command() {
+    let file = script("script_bad_substitution.sh", "echo ${x:-def}\necho $?\n");
+    let output = clawsh(&[&file], "");
+    assert_eq!(stdout(&output), "1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{file}: line 1: ${{x:-def}}: bad substitution\n")
+    );
+}
@@ -0,0 +1,32 @@
+//! Files run by `source` execute one complete command at a time
+
+mod common;
+
+use std::fs;
+
+use common::clawsh;
+
+#[test]
+fn commands_before_a_syntax_error_run() {
+    let file = format!("{}/syntax_error.sh", env!("CARGO_TARGET_TMPDIR"));
+    let src = "echo one\nalias hi='echo alias'\nhi\necho a \\\n  b\nf() {\n  echo $LINENO\n}\nf\necho )\necho never\n";
+    assert!(fs::write(&file, src).is_ok());
+    let output = clawsh(&["-c", &format!("source {file}; echo status $?")], "");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "one\nalias\na b\n7\nstatus 1\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{file}: line 10: syntax error near unexpected token `)'\n")
+    );
+}
+
+#[test]
+fn return_stops_the_file() {
+    let file = format!("{}/return.sh", env!("CARGO_TARGET_TMPDIR"));
+    assert!(fs::write(&file, "echo one\nreturn 3\necho never\n(\n").is_ok());
+    let output = clawsh(&["-c", &format!("source {file}; echo status $?")], "");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "one\nstatus 3\n");
+    assert!(output.stderr.is_empty());
+}
@@ -0,0 +1,73 @@
+//! The kind of shell decides which startup files it reads
+
+mod common;
+
+use std::fs;
+
+use common::{clawsh_at, stdout};
+
+/// A home directory with every startup file, each printing its name
+fn home(name: &str) -> String {
+    let home = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    let config = format!("{home}/.config/clawsh");
+    assert!(fs::create_dir_all(&config).is_ok());
+    for (file, marker) in [
+        (".clawsh_profile", "profile"),
+        (".clawshrc", "rc"),
+        (".clawsh_logout", "logout"),
+        ("custom_rc", "custom"),
+    ] {
+        assert!(fs::write(format!("{home}/{file}"), format!("echo {marker}\n")).is_ok());
+    }
+    assert!(fs::write(format!("{config}/rc"), "echo xdg\n").is_ok());
+    home
+}
+
+/// The startup files read by a shell started with `args`, in order
+fn startup_files(home: &str, args: &[&str]) -> Vec<String> {
+    let output = clawsh_at(home, args, "echo command\n");
+    // `/etc/profile` belongs to the system and may print anything
+    stdout(&output)
+        .lines()
+        .filter(|line| ["profile", "rc", "logout", "custom", "xdg", "command"].contains(line))
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn interactive_shell_reads_rc_file() {
+    let home = home("startup_interactive");
+    assert_eq!(startup_files(&home, &["-i"]), ["rc", "command"]);
+    let custom = format!("{home}/custom_rc");
+    assert_eq!(
+        startup_files(&home, &["-i", "--rcfile", &custom]),
+        ["custom", "command"]
+    );
+    assert_eq!(startup_files(&home, &["-i", "--norc"]), ["command"]);
+    assert!(fs::remove_file(format!("{home}/.clawshrc")).is_ok());
+    assert_eq!(startup_files(&home, &["-i"]), ["xdg", "command"]);
+}
+
+#[test]
+fn login_shell_reads_profile_and_logout_files() {
+    let home = home("startup_login");
+    assert_eq!(
+        startup_files(&home, &["-l"]),
+        ["profile", "command", "logout"]
+    );
+    assert_eq!(
+        startup_files(&home, &["-il"]),
+        ["profile", "command", "logout"]
+    );
+    assert_eq!(
+        startup_files(&home, &["--login", "--noprofile"]),
+        ["command", "logout"]
+    );
+}
+
+#[test]
+fn non_interactive_shell_reads_nothing() {
+    let home = home("startup_none");
+    assert_eq!(startup_files(&home, &[]), ["command"]);
+    assert_eq!(startup_files(&home, &["-c", "echo command"]), ["command"]);
+}
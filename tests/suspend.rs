@@ -0,0 +1,50 @@
+//! Ctrl-Z stops the foreground job, which `fg` and `bg` continue
+
+mod common;
+
+use common::terminal::Terminal;
+
+#[test]
+fn ctrl_z_stops_the_foreground_job() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.send("sh -c 'echo started; read line; echo \"read $line\"'\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x1a");
+    let stopped = terminal.expect("$ ");
+    assert!(
+        stopped.contains(
+            "[1]+  Stopped                 sh -c 'echo started; read line; echo \"read $line\"'"
+        ),
+        "{stopped:?}"
+    );
+    assert_eq!(terminal.run("echo \"status $?\""), "status 148\n");
+
+    // the job gets the terminal back and reads from it
+    terminal.send("fg\r");
+    terminal.expect("fg\r\nsh -c 'echo started; read line; echo \"read $line\"'\r\n");
+    terminal.send("input\r");
+    terminal.expect("read input\r\n");
+    terminal.expect("$ ");
+    assert_eq!(terminal.run("jobs"), "");
+}
+
+#[test]
+fn bg_continues_a_stopped_job() {
+    let mut terminal = Terminal::start(&[]);
+    terminal.send("sh -c 'sleep 0.5 & echo started; wait; echo finished'\r");
+    terminal.expect("started\r\n");
+    terminal.send("\x1a");
+    terminal.expect("Stopped");
+    terminal.expect("$ ");
+    assert_eq!(
+        terminal.run("bg"),
+        "[1]+ sh -c 'sleep 0.5 & echo started; wait; echo finished' &\n"
+    );
+    assert_eq!(
+        terminal.run("jobs"),
+        "[1]+  Running                 sh -c 'sleep 0.5 & echo started; wait; echo finished' &\n"
+    );
+    terminal.expect("finished\r\n");
+    terminal.run("wait");
+    assert_eq!(terminal.run("jobs"), "");
+}
@@ -0,0 +1,84 @@
+//! `trap` sets, resets and lists the actions run on signals and on the shell's own conditions
+
+mod common;
+
+use common::{clawsh, run, stdout};
+
+#[test]
+fn list_traps() {
+    let script = r#"
+        trap 'echo bye' EXIT
+        trap "echo it's" int
+        trap '' SIGQUIT
+        trap -p
+        echo ---
+        trap -p INT TERM
+    "#;
+    assert_eq!(
+        run(script),
+        "trap -- 'echo bye' EXIT\n\
+         trap -- 'echo it'\\''s' SIGINT\n\
+         trap -- '' SIGQUIT\n\
+         ---\n\
+         trap -- 'echo it'\\''s' SIGINT\n\
+         bye\n"
+    );
+}
+
+#[test]
+fn reset_traps() {
+    let script = r"
+        trap 'echo int' INT
+        trap 'echo quit' QUIT
+        trap 'echo term' TERM
+        trap 'echo bye' EXIT
+        trap - INT
+        trap QUIT
+        trap 15
+        trap -- - EXIT
+        trap -p
+        echo done
+    ";
+    // a lone condition, or a signal number in place of the action, resets as `-` does
+    assert_eq!(run(script), "done\n");
+}
+
+#[test]
+fn empty_action_ignores_the_signal() {
+    let script = r"
+        trap '' USR1
+        kill -USR1 $$
+        sh -c 'kill -USR1 $$; echo child survived'
+        echo survived
+    ";
+    // commands the shell runs ignore the signal as well
+    assert_eq!(run(script), "child survived\nsurvived\n");
+}
+
+#[test]
+fn list_signals() {
+    let output = run("trap -l");
+    assert_eq!(
+        output.lines().next(),
+        Some(" 1) SIGHUP\t 2) SIGINT\t 3) SIGQUIT\t 4) SIGILL\t 5) SIGTRAP")
+    );
+    assert_eq!(output, run("kill -l"));
+}
+
+#[test]
+fn invalid_arguments() {
+    let output = clawsh(
+        &[
+            "-c",
+            "trap 'echo x' NOPE USR1; echo \"$?\"; trap -x; echo \"$?\"; trap -p",
+        ],
+        "",
+    );
+    // the valid conditions are set all the same
+    assert_eq!(stdout(&output), "1\n1\ntrap -- 'echo x' SIGUSR1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "command error: trap: NOPE: invalid signal specification\n\
+         command error: trap: -x: invalid option\n"
+    );
+}
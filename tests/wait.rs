@@ -0,0 +1,67 @@
+//! `wait` sets `$?` to the status of the job or process it waited for
+
+mod common;
+
+use common::{clawsh, run, stdout};
+
+#[test]
+fn status_of_the_waited_for_job() {
+    let script = r#"
+        sh -c 'exit 3' & wait $!; echo "pid $?"
+        sh -c 'kill -TERM $$' & wait $!; echo "killed $?"
+        sh -c 'exit 4' & wait %1; echo "job $?"
+        sh -c 'exit 6' & first=$!
+        sh -c 'exit 7' & wait $first $!; echo "last $?"
+        "#;
+    assert_eq!(run(script), "pid 3\nkilled 143\njob 4\nlast 7\n");
+}
+
+#[test]
+fn wait_for_all_jobs() {
+    let script = r#"
+        sh -c 'sleep 0.2; echo late; exit 5' &
+        wait; echo "all $?"
+        wait; echo "none $?"
+        "#;
+    assert_eq!(run(script), "late\nall 0\nnone 0\n");
+}
+
+#[test]
+fn wait_for_any_job() {
+    let script = r#"
+        sh -c 'sleep 0.5; exit 1' &
+        sh -c 'exit 2' &
+        wait -n; echo "first $?"
+        wait -n; echo "second $?"
+        wait -n; echo "none $?"
+        "#;
+    assert_eq!(run(script), "first 2\nsecond 1\nnone 127\n");
+}
+
+#[test]
+fn unknown_ids() {
+    let output = clawsh(
+        &[
+            "-c",
+            r#"wait 1; echo "pid $?"; wait %3; echo "job $?"; sh -c 'exit 3' & wait %3 $!; echo "last $?""#,
+        ],
+        "",
+    );
+    assert_eq!(stdout(&output), "pid 127\njob 127\nlast 3\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "command error: wait: pid 1 is not a child of this shell\n\
+         command error: wait: %3: no such job\n\
+         command error: wait: %3: no such job\n"
+    );
+}
+
+#[test]
+fn trapped_signal_ends_the_wait() {
+    let script = r#"
+        trap 'echo trapped' USR1
+        sh -c 'sleep 0.2; kill -USR1 $PPID; sleep 1' &
+        wait $!; echo "wait $?"
+        "#;
+    assert_eq!(run(script), "trapped\nwait 138\n");
+}